@@ -1,76 +1,148 @@
 mod add;
+mod doctor;
+mod duplicate;
+mod edit;
+mod export;
+mod import;
+mod info;
 mod list;
+mod migrate;
 mod remove;
+mod rename;
+mod search;
 mod spawn;
+mod tag;
+mod update;
+mod validate;
 mod version;
 mod help;
 
 use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, StdoutLock, Write};
+use std::process;
 use crate::commands::add::Add;
+use crate::commands::doctor::Doctor;
+use crate::commands::duplicate::Duplicate;
+use crate::commands::edit::Edit;
+use crate::commands::export::Export;
+use crate::commands::import::Import;
 use crate::commands::help::Help;
+use crate::commands::info::Info;
 use crate::commands::list::List;
+use crate::commands::migrate::Migrate;
 use crate::commands::remove::Remove;
+use crate::commands::rename::Rename;
+use crate::commands::search::Search;
 use crate::commands::spawn::Spawn;
+use crate::commands::tag::Tag;
+use crate::commands::update::Update;
+use crate::commands::validate::Validate;
 use crate::commands::version::Version;
+use crate::templates::report::OutputFormat;
+use crate::templates::TemplateError;
 // region: Command Trait
 
 pub trait Command
 {
-    fn execute(flags: HashMap<String, String>);
+    /// Flags the command takes more than once, e.g. `--exclude`. Any other flag given twice is
+    /// refused instead of guessing which value was meant.
+    const REPEATABLE_FLAGS: &[&str] = &[];
+
+    fn execute(flags: HashMap<String, Vec<String>>);
     fn show_usage();
 }
 
-pub fn try_execute(s: &str) -> Result<(), CommandBuildError>
+pub fn try_execute(args: &[String]) -> Result<(), CommandBuildError>
 {
-    let mut parts = s.split_whitespace();
-
-    let main_command = match parts.next() {
-        Some(command) => command,
+    let (main_command, command_instr) = match args.split_first() {
+        Some((command, command_instr)) => (command.as_str(), command_instr),
         None => return Err(CommandBuildError::NotEnoughArgsIntroduced),
     };
 
-    let command_instr = parts.map(String::from).collect::<Vec<String>>();
-    let flags = map_flags(&command_instr);
+    let flags = map_flags(command_instr);
 
     match main_command {
-        "spawn" => Ok(Spawn::execute(flags)),
-        "add" => Ok(Add::execute(flags)),
-        "rm" => Ok(Remove::execute(flags)),
-        "list" => Ok(List::execute(flags)),
-        "version" => Ok(Version::execute(flags)),
-        _ => Ok(Help::execute(flags)),
+        "spawn" => run::<Spawn>(flags),
+        "add" => run::<Add>(flags),
+        "rm" => run::<Remove>(flags),
+        "mv" => run::<Rename>(flags),
+        "cp" => run::<Duplicate>(flags),
+        "export" => run::<Export>(flags),
+        "import" => run::<Import>(flags),
+        "list" => run::<List>(flags),
+        "search" => run::<Search>(flags),
+        "tag" => run::<Tag>(flags),
+        "info" => run::<Info>(flags),
+        "edit" => run::<Edit>(flags),
+        "update" => run::<Update>(flags),
+        "validate" => run::<Validate>(flags),
+        "migrate" => run::<Migrate>(flags),
+        "doctor" => run::<Doctor>(flags),
+        "version" => run::<Version>(flags),
+        _ => run::<Help>(flags),
+    }
+}
+
+fn run<C: Command>(flags: HashMap<String, Vec<String>>) -> Result<(), CommandBuildError>
+{
+    if let Some(flag) = repeated_flag(&flags, C::REPEATABLE_FLAGS) {
+        return Err(CommandBuildError::RepeatedFlag(flag.to_string()));
     }
+
+    C::execute(flags);
+    Ok(())
 }
 
 // endregion: Command Trait
 
 // region: Command Build Error
 
-#[derive(Debug)]
 pub enum CommandBuildError
 {
     NotEnoughArgsIntroduced,
+    RepeatedFlag(String),
+}
+
+impl Debug for CommandBuildError
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        match self {
+            CommandBuildError::NotEnoughArgsIntroduced => write!(f, "NotEnoughArgsIntroduced"),
+            CommandBuildError::RepeatedFlag(flag) => write!(f, "{} can only be given once.", flag),
+        }
+    }
 }
 
 // endregion: Command Build Error
 
-fn map_flags(args: &Vec<String>) -> HashMap<String, String>
+/// Arguments that don't follow a flag are kept in order under this key. Flags always start
+/// with a dash, so it never clashes with one.
+const POSITIONAL_ARGS: &str = "args";
+
+/// Maps every flag to its values, in the order they were given. A flag without a value gets an
+/// empty one.
+fn map_flags(args: &[String]) -> HashMap<String, Vec<String>>
 {
-    let mut hash_map = HashMap::new();
+    let mut hash_map: HashMap<String, Vec<String>> = HashMap::new();
     let mut i = 0;
 
     while i < args.len() {
         let actual_word = &args[i];
 
         if is_flag(actual_word) {
-            if i + 1 < args.len() && !is_flag(&args[i + 1]) {
-                hash_map.insert(actual_word.to_string(), args[i + 1].clone());
+            let value = if i + 1 < args.len() && !is_flag(&args[i + 1]) {
                 i += 2;
+                args[i - 1].clone()
             } else {
-                hash_map.insert(actual_word.to_string(), String::new());
                 i += 1;
-            }
+                String::new()
+            };
+
+            hash_map.entry(actual_word.to_string()).or_default().push(value);
         } else {
+            hash_map.entry(POSITIONAL_ARGS.to_string()).or_default().push(actual_word.to_string());
             i += 1;
         }
     }
@@ -78,9 +150,76 @@ fn map_flags(args: &Vec<String>) -> HashMap<String, String>
     hash_map
 }
 
+/// Finds a flag given more than once that the command only takes once, e.g. `-n a -n b`.
+fn repeated_flag<'a>(flags: &'a HashMap<String, Vec<String>>, repeatable_flags: &[&str]) -> Option<&'a str>
+{
+    flags
+        .iter()
+        .filter(|(flag, values)| values.len() > 1 && *flag != POSITIONAL_ARGS)
+        .map(|(flag, _)| flag.as_str())
+        .find(|flag| !repeatable_flags.contains(flag))
+}
+
+/// Returns the value of a flag given once, e.g. `-n name`.
+pub fn flag_value<'a>(flags: &'a HashMap<String, Vec<String>>, flag: &str) -> Option<&'a str>
+{
+    flags.get(flag).and_then(|values| values.first()).map(String::as_str)
+}
+
+/// Returns every value given to a flag that can be repeated, e.g. `--exclude a --exclude b`.
+pub fn flag_values<'a>(flags: &'a HashMap<String, Vec<String>>, flag: &str) -> Vec<&'a str>
+{
+    match flags.get(flag) {
+        Some(values) => values
+            .iter()
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Adds a value to a flag that can be repeated, as if it was given once more.
+pub fn push_flag_value(flags: &mut HashMap<String, Vec<String>>, flag: &str, value: &str)
+{
+    flags.entry(flag.to_string()).or_default().push(value.to_string());
+}
+
+/// Returns the arguments that don't follow a flag, e.g. the names of `mv <old> <new>`.
+pub fn positional_args(flags: &HashMap<String, Vec<String>>) -> Vec<&str>
+{
+    flag_values(flags, POSITIONAL_ARGS)
+}
+
+/// Format asked with `--format`, free-form text without it.
+pub fn output_format(flags: &HashMap<String, Vec<String>>) -> Result<Option<OutputFormat>, TemplateError>
+{
+    flag_value(flags, "--format").map(|format| format.parse::<OutputFormat>()).transpose()
+}
+
+/// Writes the output of a command to the locked stdout. A closed pipe, e.g. `| head -1`, only
+/// means the rest isn't read, so it isn't an error.
+pub fn write_output(write: impl FnOnce(&mut StdoutLock) -> io::Result<()>)
+{
+    let mut stdout = io::stdout().lock();
+
+    match write(&mut stdout).and_then(|_| stdout.flush()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => fail(format!("Error writing the output: {}", e)),
+        _ => {}
+    }
+}
+
+/// Ends a command that failed, with the error in the stderr and a non-zero exit code.
+pub fn fail(message: impl Display) -> !
+{
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+/// A lone dash is a value, e.g. `--archive -` for the stdout.
 fn is_flag(s: &str) -> bool
 {
-    s.starts_with("-") || s.starts_with("--")
+    s.starts_with("-") && s != "-"
 }
 
 #[cfg(test)]
@@ -91,26 +230,61 @@ mod tests
     #[test]
     fn test_map_flags()
     {
-        let args = vec!["-n", "name", "a", "-a", "-p", "path", "-r", "-as-dir"];
-        let result = map_flags(&args.iter().map(|s| s.to_string()).collect());
+        let args = ["-n", "name", "a", "-a", "-p", "path", "-r", "-as-dir"];
+        let result = map_flags(&args.iter().map(|s| s.to_string()).collect::<Vec<String>>());
+
+        assert_eq!(flag_value(&result, "-n").unwrap(), "name");
+        assert_eq!(flag_value(&result, "-p").unwrap(), "path");
+        assert_eq!(flag_value(&result, "-r").unwrap(), "");
+        assert_eq!(flag_value(&result, "-a").unwrap(), "");
+        assert_eq!(flag_value(&result, "-as-dir").unwrap(), "");
 
-        assert_eq!(result.get("-n").unwrap(), "name");
-        assert_eq!(result.get("-p").unwrap(), "path");
-        assert_eq!(result.get("-r").unwrap(), "");
-        assert_eq!(result.get("-a").unwrap(), "");
-        assert_eq!(result.get("-as-dir").unwrap(), "");
+        let args = ["mkt", "remove", "-n", "crates"];
+        let result = map_flags(&args.iter().map(|s| s.to_string()).collect::<Vec<String>>());
 
-        let args = vec!["mkt", "remove", "-n", "crates"];
-        let result = map_flags(&args.iter().map(|s| s.to_string()).collect());
+        assert_eq!(flag_value(&result, "-n").unwrap(), "crates");
+    }
+
+    #[test]
+    fn test_repeated_flags()
+    {
+        let args = ["add", "--exclude", "target/", "-p", "path", "--exclude", "*.log", "--exclude"];
+        let result = map_flags(&args.iter().map(|s| s.to_string()).collect::<Vec<String>>());
+
+        assert_eq!(flag_values(&result, "--exclude"), vec!["target/", "*.log"]);
+        assert_eq!(flag_values(&result, "-p"), vec!["path"]);
+        assert!(flag_values(&result, "-n").is_empty());
+
+        assert_eq!(repeated_flag(&result, &["--exclude"]), None);
+        assert_eq!(repeated_flag(&result, &[]), Some("--exclude"));
+    }
+
+    #[test]
+    fn test_single_flags()
+    {
+        let args = ["-n", "a", "-n", "b", "--exclude", "target/"];
+        let result = map_flags(&args.iter().map(|s| s.to_string()).collect::<Vec<String>>());
+
+        assert_eq!(repeated_flag(&result, &["--exclude"]), Some("-n"));
+        assert_eq!(flag_values(&result, "--exclude"), vec!["target/"]);
+    }
+
+    #[test]
+    fn test_positional_args()
+    {
+        let args = ["old", "-a", "new", "-n", "name", "other"];
+        let result = map_flags(&args.iter().map(|s| s.to_string()).collect::<Vec<String>>());
 
-        assert_eq!(result.get("-n").unwrap(), "crates");
+        assert_eq!(positional_args(&result), vec!["old", "other"]);
+        assert_eq!(flag_value(&result, "-a").unwrap(), "new");
     }
 
     #[test]
     fn test_is_flag()
     {
-        assert_eq!(is_flag("-n"), true);
-        assert_eq!(is_flag("--name"), true);
-        assert_eq!(is_flag("name"), false);
+        assert!(is_flag("-n"));
+        assert!(is_flag("--name"));
+        assert!(!is_flag("name"));
+        assert!(!is_flag("-"));
     }
 }
@@ -1,4 +1,4 @@
-use crate::commands::Command;
+use crate::commands::{flag_value, Command};
 use crate::{templates, BIN_NAME};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -7,8 +7,10 @@ pub struct Add;
 
 impl Command for Add
 {
-    fn execute(flags: HashMap<String, String>) {
-        let template_path = match flags.get("-p") {
+    const REPEATABLE_FLAGS: &[&str] = &["--exclude", "--templatize"];
+
+    fn execute(flags: HashMap<String, Vec<String>>) {
+        let template_path = match flag_value(&flags, "-p") {
             Some(path) => path,
             None => {
                 Self::show_usage();
@@ -16,9 +18,10 @@ impl Command for Add
             }
         };
 
-        let template_name = match flags.get("-n") {
-            Some(name) => name,
-            None => &extract_name_from_path(template_path),
+        let template_name = match (flag_value(&flags, "-n"), flag_value(&flags, "--subdir")) {
+            (Some(name), _) => name,
+            (None, Some(subdir)) if !subdir.is_empty() => &extract_name_from_path(subdir.trim_end_matches('/')),
+            _ => &extract_name_from_path(template_path),
         };
 
         if flags.contains_key("-r") || templates::get_template_data_path(template_name).is_none() {
@@ -38,13 +41,13 @@ impl Command for Add
 
 fn extract_name_from_path(path: &str) -> String
 {
-    // Extracting the file name from the path
-    let path = PathBuf::from(path);
+    // Extracting the file name from the path, urls and shorthands (gh:org/repo) included
+    let path = PathBuf::from(path.trim_end_matches('/'));
     let file_name = path
         .file_name()
-        .expect("Should have a name")
-        .to_str()
-        .expect("Should exist");
+        .and_then(|file_name| file_name.to_str())
+        .unwrap_or_default();
+    let file_name = file_name.rsplit(':').next().unwrap_or(file_name);
 
     // Removing the extension from the file name
     file_name.split('.').collect::<Vec<&str>>()[0].to_string()
@@ -0,0 +1,75 @@
+use crate::commands::Command;
+use crate::templates::health;
+use crate::BIN_NAME;
+use std::collections::HashMap;
+
+pub struct Doctor;
+
+impl Command for Doctor
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        if flags.contains_key("--fix") {
+            fix();
+            return;
+        }
+
+        let mut problems = health::check_installation();
+        problems.extend(health::check_store());
+        problems.extend(health::check_templates());
+
+        if problems.is_empty() {
+            println!("No problems found.");
+            return;
+        }
+
+        for problem in &problems {
+            match problem.is_fixable() {
+                true => println!("{}: {}. With --fix: {}.", problem.name, problem.description, problem.fix_description()),
+                false => println!("{}: {}.", problem.name, problem.description),
+            }
+        }
+
+        if problems.iter().any(health::Problem::is_fixable) {
+            println!("Run {} doctor --fix to fix them.", BIN_NAME);
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} doctor [--fix]",
+            BIN_NAME
+        );
+    }
+}
+
+fn fix()
+{
+    let fixed = match health::fix_store() {
+        Ok(fixed) => fixed,
+        Err(e) => {
+            println!("Error fixing the templates: {:?}", e);
+            return;
+        }
+    };
+
+    for (problem, result) in &fixed {
+        match result {
+            Ok(_) => println!("{}: {}, {}.", problem.name, problem.description, problem.fix_description()),
+            Err(e) => println!("{}: {}, couldn't be fixed: {:?}", problem.name, problem.description, e),
+        }
+    }
+
+    let remaining = health::check_store();
+
+    for problem in &remaining {
+        println!("{}: {}. {}.", problem.name, problem.description, problem.fix_description());
+    }
+
+    if fixed.is_empty() && remaining.is_empty() {
+        println!("No problems found.");
+    } else if !fixed.is_empty() && health::QUARANTINE_DIR.exists() {
+        println!("Quarantined entries are kept in {}.", health::QUARANTINE_DIR.display());
+    }
+}
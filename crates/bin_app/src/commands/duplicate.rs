@@ -0,0 +1,24 @@
+use crate::commands::{positional_args, Command};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Duplicate;
+
+impl Command for Duplicate
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        match positional_args(&flags)[..] {
+            [src, dst] => templates::copy_template(src, dst),
+            _ => Self::show_usage(),
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} cp <Template Name> <Name of the copy>",
+            BIN_NAME
+        );
+    }
+}
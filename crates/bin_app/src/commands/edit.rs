@@ -0,0 +1,29 @@
+use crate::commands::{flag_value, Command};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Edit;
+
+impl Command for Edit
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let template_name = match flag_value(&flags, "-n") {
+            Some(name) => name,
+            None => {
+                Self::show_usage();
+                return;
+            }
+        };
+
+        templates::edit_template(template_name)
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} edit -n <Template Name>",
+            BIN_NAME
+        );
+    }
+}
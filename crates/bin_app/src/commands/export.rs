@@ -0,0 +1,55 @@
+use crate::commands::{flag_value, flag_values, Command};
+use crate::templates::bundle;
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+use std::path::Path;
+
+pub struct Export;
+
+/// Bundle written when no -o is given.
+const DEFAULT_BUNDLE: &str = "templates.tar.gz";
+
+impl Command for Export
+{
+    const REPEATABLE_FLAGS: &[&str] = &["-n"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let mut names = flag_values(&flags, "-n")
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        // Every template is exported when none is named
+        if names.is_empty() {
+            names = templates::get_available_templates()
+                .into_iter()
+                .map(|(_, name)| name)
+                .collect();
+            names.sort();
+        }
+
+        if names.is_empty() {
+            println!("There are no templates to export.");
+            return;
+        }
+
+        let output = flag_value(&flags, "-o")
+            .filter(|output| !output.is_empty())
+            .unwrap_or(DEFAULT_BUNDLE);
+        let names = names.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        match bundle::export(&names, Path::new(output)) {
+            Ok(_) => println!("Exported {} template(s) to {}.", names.len(), output),
+            Err(e) => println!("Error exporting the templates: {:?}", e),
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} export [-n <Template Name>]... [-o <Bundle>]",
+            BIN_NAME
+        );
+    }
+}
@@ -5,13 +5,31 @@ use crate::commands::Command;
 pub struct Help;
 
 impl Command for Help {
-    fn execute(_flags: HashMap<String, String>) {
+    fn execute(_flags: HashMap<String, Vec<String>>) {
         let help_message = r#"
 Usage:
-    mkt [add -p <Path to the template you want to add> [-n <Custom name for the template>] [-as-dir]],
-        [list],
+    mkt [add -p <Path to the template you want to add> [-n <Custom name for the template>] [-as-dir]
+            [--type git|dir|txml|archive] [--keep-root]
+            [--exclude <Glob>]... [--gitignore] [--templatize <Literal>=<Variable>]... [--templatize-cases]
+            [--ref <Branch, tag or commit>] [--subdir <Path inside the repository>]
+            [--history keep|strip|fresh]],
+        [list [-d] [--tag <Tag>]... [--sort name|created|updated|spawned] [--format json|yaml|table]],
+        [search <Text> [--tag <Tag>]... [--sort name|created|updated|spawned]],
+        [tag -n <Name of the template you want to tag> [add|rm <Tag>...]],
+        [info -n <Name of the template you want to inspect> [--format json|yaml|table]],
+        [validate [-n <Name of the template you want to validate>]... [--format json|yaml|table]],
+        [edit -n <Name of the template you want to edit>],
+        [update [-n <Name of the git template you want to update>]],
+        [migrate],
+        [doctor [--fix]],
         [rm -n <Name of the template you want to remove>],
-        [spawn -n <Name of the template you want to spawn> [-o <Define an output name>]],
+        [mv <Name of the template you want to rename> <New name>],
+        [cp <Name of the template you want to copy> <Name of the copy>],
+        [export [-n <Name of the template you want to export>]... [-o <Bundle>]],
+        [import <Bundle> [--rename <Name in the bundle>=<New name>]... [-r]],
+        [spawn -n <Name of the template you want to spawn> [-o <Define an output name>] [--ref <Branch, tag or commit>]
+            [--history keep|strip|fresh] [--offline] [--var <Variable>=<Value>]... [--confirm]
+            [--archive <Archive> [--allow-commands]]],
         [help],
         [version]
 
@@ -20,16 +38,105 @@ Options:
                 -p <Path>       Path to the template you want to add.
                 -n <Name>       Optional: Custom name for the template.
                 -as-dir         Optional: Treat the path as a directory template.
+                --type <Type>   Optional: Add the path as a git, dir, txml or archive template instead
+                                of detecting its type. Urls, user@host:path addresses, bare
+                                repositories and working copies are detected as git templates.
+                                Shorthands like gh:org/repo are expanded, see config.toml.
+                                .zip, .tar, .tar.gz and .tgz files are extracted and added as a
+                                directory.
+                --keep-root     Optional: Keep the single top-level folder of an archive, which is
+                                skipped by default.
+                --exclude <Glob> Optional: Skip the matching entries of a directory. Can be repeated.
+                --gitignore     Optional: Also skip the entries matched by the directory's .gitignore.
+                                A .mktignore file in the directory is always honoured.
+                --templatize <Literal>=<Variable>
+                                Optional: Replace the literal in names and contents of a directory
+                                with ${Variable}. Can be repeated.
+                --templatize-cases
+                                Optional: Also replace the case variants of the literals (MyProj, my_proj...).
+                --ref <Ref>     Optional: Pin a git template to a branch, tag or commit.
+                --subdir <Path> Optional: Only spawn this subdirectory of a git template. If it holds
+                                a TXML file, it is spawned as a TXML template. Names the template by default.
+                --history <Mode> Optional: What to do with the repository of a spawned git template:
+                                keep it (default), strip the .git directory or start a fresh one
+                                with an initial commit.
 
-    list        List all available templates.
+    list        List all available templates, with their tags.
                 -d              Optional: Show the description of the templates.
+                --tag <Tag>     Optional: Only list the templates with this tag, can be repeated.
+                --sort <Order>  Optional: Sort by name (default) or newest first by created, updated
+                                or spawned date.
+                --format <Format> Optional: Write json, yaml or a table with the name, class, source,
+                                description, version, tags and variables of each template.
+
+    search      List the templates whose name, description, tags or author contain the text.
+                <Text>          Text to search, without case.
+                --tag <Tag>     Optional: Only search the templates with this tag, can be repeated.
+                --sort <Order>  Optional: Same as in list.
+
+    tag         Show the tags of a template, or add or remove them. TXML templates are added with
+                the tags of their Metadata.
+                -n <Name>       Name of the template.
+                add <Tag>...    Optional: Add the tags.
+                rm <Tag>...     Optional: Remove the tags.
+
+    info        Show all the information about a template.
+                -n <Name>       Name of the template to inspect.
+                --format <Format> Optional: Write json, yaml or a table, with the fields of list first.
+
+    validate    Check that the templates can be spawned: their payload exists and is valid and the
+                remotes of the git templates are reachable.
+                -n <Name>       Optional: Template to validate, can be repeated. Every template by default.
+                --format <Format> Optional: Write json, yaml or a table with the name, valid and problems
+                                of each template.
+
+    update      Fetch the new commits of the git templates into their local mirrors and refresh
+                their descriptions.
+                -n <Name>       Optional: Only update this template.
+
+    edit        Open a stored template in $VISUAL or $EDITOR. A TXML template is only saved if it is
+                still valid when the editor is closed, otherwise it can be re-opened or reverted.
+                -n <Name>       Name of the template to edit.
+
+    migrate     Rewrite the absolute paths saved by older versions relative to the templates' dir.
+
+    doctor      Check MKT_HOME, git and the stored templates and report the problems found.
+                --fix           Optional: Repair the broken entries or move them to the quarantine dir.
 
     rm          Remove a template by name.
                 -n <Name>       Name of the template to remove.
 
-    spawn       Spawn a template by name.
+    mv          Rename a template, its stored files included.
+                <Name> <New>    Current and new name of the template.
+
+    cp          Copy a template, its stored files included, under a new name.
+                <Name> <Copy>   Name of the template and of the copy.
+
+    export      Pack templates in a tar.gz bundle that can be imported elsewhere. Git templates only
+                take their url, ref and options.
+                -n <Name>       Optional: Template to export, can be repeated. Every template by default.
+                -o <Bundle>     Optional: Path of the bundle. templates.tar.gz by default.
+
+    import      Add the templates of a bundle. Each one is validated before it is added.
+                <Bundle>        Path of the bundle.
+                --rename <Name>=<New> Optional: Import a template under another name, can be repeated.
+                -r              Optional: Replace the templates that already exist.
+
+    spawn       Spawn a template by name. Without -n, on a terminal, the template is picked from a
+                filterable list, its variables are asked and the files are shown before they are written.
                 -n <Name>       Name of the template to spawn.
                 -o <Output>     Optional: Define a custom output name for the spawned template.
+                --ref <Ref>     Optional: Use this branch, tag or commit instead of the pinned one (git templates).
+                --history <Mode> Optional: Override the history mode of a git template.
+                --offline       Optional: Never access the network. Git templates are spawned from
+                                their local mirror, which must already exist.
+                --var <Variable>=<Value> Optional: Value of a variable, which isn't asked. Can be repeated.
+                --confirm       Optional: Show the files that will be written and ask before writing them.
+                                The TXML commands and the fresh history only happen once confirmed.
+                --archive <Archive> Optional: Write the spawned files to a .zip, .tar or .tar.gz archive
+                                instead of the current dir. With - a tar is written to the stdout.
+                --allow-commands Optional: Run the TXML commands when spawning into an archive,
+                                they are skipped by default.
 
     help        Show this help message.
 
@@ -0,0 +1,50 @@
+use crate::commands::{flag_value, flag_values, positional_args, Command};
+use crate::templates::bundle::{self, ImportOptions};
+use crate::BIN_NAME;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub struct Import;
+
+impl Command for Import
+{
+    const REPEATABLE_FLAGS: &[&str] = &["--rename"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let bundle_path = match positional_args(&flags).first().copied().or(flag_value(&flags, "-p")) {
+            Some(path) if !path.is_empty() => path.to_string(),
+            _ => {
+                Self::show_usage();
+                return;
+            }
+        };
+
+        let renames = match bundle::parse_renames(&flag_values(&flags, "--rename")) {
+            Ok(renames) => renames,
+            Err(e) => {
+                println!("Error importing the templates: {:?}", e);
+                return;
+            }
+        };
+
+        let options = ImportOptions {
+            renames,
+            replace: flags.contains_key("-r"),
+        };
+
+        match bundle::import(Path::new(&bundle_path), &options) {
+            Ok(imported) if imported.is_empty() => println!("No template was imported."),
+            Ok(imported) => println!("Imported {}.", imported.join(", ")),
+            Err(e) => println!("Error importing the templates: {:?}", e),
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} import <Bundle> [--rename <Name in the bundle>=<New name>]... [-r]",
+            BIN_NAME
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::commands::{fail, flag_value, output_format, write_output, Command};
+use crate::templates::data::format_timestamp;
+use crate::templates::report::{self, InfoReport};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+use std::io::Write;
+
+pub struct Info;
+
+impl Command for Info
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let template_name = match flag_value(&flags, "-n") {
+            Some(name) => name,
+            None => {
+                Self::show_usage();
+                return;
+            }
+        };
+
+        let template_data = match templates::get_template_data(template_name) {
+            Some(template_data) => template_data,
+            None => fail(format!("Template {} not found.", template_name)),
+        };
+
+        let format = match output_format(&flags) {
+            Ok(format) => format,
+            Err(e) => fail(format!("Error showing the template: {:?}", e)),
+        };
+
+        if let Some(format) = format {
+            match report::render(&InfoReport::new(&template_data, template_name), format) {
+                Ok(output) => write_output(|out| writeln!(out, "{}", output)),
+                Err(e) => fail(format!("Error showing the template: {:?}", e)),
+            }
+            return;
+        }
+
+        write_output(|out| {
+            writeln!(out, "Name: {}", template_name)?;
+            writeln!(out, "Class: {}", template_data.class())?;
+            writeln!(out, "Path: {}", template_data.location())?;
+            match template_data.to_template() {
+                Ok(template) => writeln!(out, "Description: {}", template.get_description())?,
+                Err(e) => writeln!(out, "Description: {:?}", e)?,
+            }
+
+            if let Some(source) = template_data.source() {
+                writeln!(out, "Source: {}", source)?;
+            }
+
+            writeln!(out, "Created: {}", format_timestamp(template_data.created_at()))?;
+            writeln!(out, "Updated: {}", format_timestamp(template_data.updated_at()))?;
+
+            match template_data.last_spawned() {
+                Some(last_spawned) => writeln!(out, "Last spawned: {}", format_timestamp(last_spawned))?,
+                None => writeln!(out, "Last spawned: never")?,
+            }
+
+            if !template_data.tags().is_empty() {
+                writeln!(out, "Tags: {}", template_data.tags().join(", "))?;
+            }
+
+            if let Some(checksum) = template_data.checksum() {
+                writeln!(out, "Checksum: {}", checksum)?;
+            }
+
+            if let Some(git_ref) = template_data.git_ref() {
+                writeln!(out, "Ref: {}", git_ref)?;
+            }
+
+            if let Some(subdir) = template_data.subdir() {
+                writeln!(out, "Subdir: {}", subdir)?;
+            }
+
+            if let Some(history) = template_data.history() {
+                writeln!(out, "History: {}", history)?;
+            }
+
+            if template_data.ignore().is_empty() {
+                writeln!(out, "Ignored: -")?;
+            } else {
+                writeln!(out, "Ignored: {}", template_data.ignore().join(", "))?;
+            }
+
+            Ok(())
+        });
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} info -n <Template Name> [--format json|yaml|table]",
+            BIN_NAME
+        );
+    }
+}
@@ -1,21 +1,91 @@
 use std::collections::HashMap;
-use crate::commands::Command;
+use std::io::{self, Write};
+use crate::commands::{fail, flag_value, flag_values, output_format, write_output, Command};
 use crate::templates;
+use crate::templates::data::TemplateData;
+use crate::templates::report::{self, TemplateReport};
+use crate::templates::search::{self, SortOrder};
+use crate::BIN_NAME;
 
 pub struct List;
 
 impl Command for List{
-    fn execute(_flags: HashMap<String, String>)
+    const REPEATABLE_FLAGS: &[&str] = &["--tag"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
     {
-        for (template_data, template_name) in templates::get_available_templates() {
-            println!("{}", template_name);
-            
-            if _flags.contains_key("-d") { 
-                println!("----| Description: {}", template_data.to_template().get_description());
+        let (templates, format) = match listed_templates(&flags).and_then(|templates| Ok((templates, output_format(&flags)?))) {
+            Ok(listed) => listed,
+            Err(e) => fail(format!("Error listing the templates: {:?}", e)),
+        };
+
+        match format {
+            Some(format) => {
+                let reports = templates
+                    .iter()
+                    .map(|(template_data, name)| TemplateReport::new(template_data, name))
+                    .collect::<Vec<_>>();
+
+                match report::render(&reports, format) {
+                    Ok(output) => write_output(|out| writeln!(out, "{}", output)),
+                    Err(e) => fail(format!("Error listing the templates: {:?}", e)),
+                }
             }
+            None => write_output(|out| print_templates(out, &templates, flags.contains_key("-d"))),
+        }
+
+        let problems = templates::health::check_store();
+
+        // The stdout only carries the report when it is formatted
+        if !problems.is_empty() {
+            eprintln!("{} broken entr{} in the templates' dir. Run {} doctor for details.",
+                problems.len(),
+                if problems.len() == 1 { "y" } else { "ies" },
+                BIN_NAME
+            );
         }
     }
 
     fn show_usage() {
+        println!(
+            "USAGE: {} list [-d] [--tag <Tag>]... [--sort name|created|updated|spawned] [--format json|yaml|table]",
+            BIN_NAME
+        );
     }
-}
\ No newline at end of file
+}
+
+/// The templates with every `--tag`, sorted as asked with `--sort` or by name.
+pub fn listed_templates(flags: &HashMap<String, Vec<String>>) -> Result<Vec<(TemplateData, String)>, templates::TemplateError>
+{
+    let order = match flag_value(flags, "--sort") {
+        Some(order) => order.parse::<SortOrder>()?,
+        None => SortOrder::Name,
+    };
+
+    let tags = flag_values(flags, "--tag");
+    let mut templates = templates::get_available_templates();
+
+    templates.retain(|(template_data, _)| search::has_tags(template_data, &tags));
+    search::sort(&mut templates, order);
+
+    Ok(templates)
+}
+
+pub fn print_templates(out: &mut impl Write, templates: &[(TemplateData, String)], descriptions: bool) -> io::Result<()>
+{
+    for (template_data, template_name) in templates {
+        match template_data.tags().is_empty() {
+            true => writeln!(out, "{}", template_name)?,
+            false => writeln!(out, "{} [{}]", template_name, template_data.tags().join(", "))?,
+        }
+
+        if descriptions {
+            match template_data.to_template() {
+                Ok(template) => writeln!(out, "----| Description: {}", template.get_description())?,
+                Err(e) => writeln!(out, "----| Description: {:?}", e)?,
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use crate::commands::Command;
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Migrate;
+
+impl Command for Migrate
+{
+    fn execute(_flags: HashMap<String, Vec<String>>)
+    {
+        templates::migrate_paths();
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} migrate",
+            BIN_NAME
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::commands::Command;
+use crate::commands::{flag_value, Command};
 use crate::{templates, BIN_NAME};
 use std::collections::HashMap;
 
@@ -6,9 +6,9 @@ pub struct Remove;
 
 impl Command for Remove
 {
-    fn execute(flags: HashMap<String, String>)
+    fn execute(flags: HashMap<String, Vec<String>>)
     {
-        let template_name = match flags.get("-n") {
+        let template_name = match flag_value(&flags, "-n") {
             Some(name) => name,
             None => {
                 Self::show_usage();
@@ -0,0 +1,24 @@
+use crate::commands::{positional_args, Command};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Rename;
+
+impl Command for Rename
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        match positional_args(&flags)[..] {
+            [old, new] => templates::move_template(old, new),
+            _ => Self::show_usage(),
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} mv <Template Name> <New Name>",
+            BIN_NAME
+        );
+    }
+}
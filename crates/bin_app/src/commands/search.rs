@@ -0,0 +1,44 @@
+use crate::commands::list::{listed_templates, print_templates};
+use crate::commands::{fail, positional_args, write_output, Command};
+use crate::templates::search;
+use crate::BIN_NAME;
+use std::collections::HashMap;
+
+pub struct Search;
+
+impl Command for Search
+{
+    const REPEATABLE_FLAGS: &[&str] = &["--tag"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let text = positional_args(&flags).join(" ");
+
+        if text.is_empty() {
+            Self::show_usage();
+            return;
+        }
+
+        let mut templates = match listed_templates(&flags) {
+            Ok(templates) => templates,
+            Err(e) => fail(format!("Error searching the templates: {:?}", e)),
+        };
+
+        templates.retain(|(template_data, name)| search::matches(template_data, name, &text));
+
+        if templates.is_empty() {
+            println!("No templates match {}.", text);
+            return;
+        }
+
+        write_output(|out| print_templates(out, &templates, true));
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} search <Text> [--tag <Tag>]... [--sort name|created|updated|spawned]",
+            BIN_NAME
+        );
+    }
+}
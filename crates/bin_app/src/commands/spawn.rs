@@ -1,22 +1,30 @@
-use crate::commands::Command;
+use crate::commands::{flag_value, Command};
 use crate::{templates, BIN_NAME};
 use std::collections::HashMap;
+use std::io::{self, IsTerminal};
 
 pub struct Spawn;
 
 impl Command for Spawn
 {
-    fn execute(flags: HashMap<String, String>)
+    const REPEATABLE_FLAGS: &[&str] = &["--var"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
     {
-        let template_name = match flags.get("-n") {
+        let template_name = match flag_value(&flags, "-n") {
             Some(name) => name,
+            // Only someone at a terminal can pick the template
+            None if io::stdin().is_terminal() && io::stdout().is_terminal() => {
+                templates::spawn_interactive(flags);
+                return;
+            }
             None => {
                 Self::show_usage();
                 return;
             }
         };
 
-        let template_output_name = flags.get("-o").unwrap_or_else(|| template_name);
+        let template_output_name = flag_value(&flags, "-o").unwrap_or(template_name);
 
         templates::generate(template_name, template_output_name, flags.clone());
     }
@@ -24,7 +32,7 @@ impl Command for Spawn
     fn show_usage()
     {
         println!(
-            "USAGE: {} spawn -n <Template Name> [-o <Spawn name (Some templates can`t use it)>]",
+            "USAGE: {} spawn -n <Template Name> [-o <Spawn name (Some templates can`t use it)>] [--var <Variable>=<Value>]... [--confirm] [--archive <File.zip|.tar|.tar.gz or - for the stdout> [--allow-commands]]",
             BIN_NAME
         );
     }
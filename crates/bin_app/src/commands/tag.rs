@@ -0,0 +1,46 @@
+use crate::commands::{flag_value, positional_args, Command};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Tag;
+
+impl Command for Tag
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let template_name = match flag_value(&flags, "-n") {
+            Some(name) => name,
+            None => {
+                Self::show_usage();
+                return;
+            }
+        };
+
+        let result = match positional_args(&flags)[..] {
+            [] => match templates::get_template_data(template_name) {
+                Some(template_data) => Ok(template_data.tags().to_vec()),
+                None => Err(templates::TemplateError::TemplateNotFound(template_name.to_string())),
+            },
+            ["add", ref tags @ ..] if !tags.is_empty() => templates::tag_template(template_name, tags, false),
+            ["rm", ref tags @ ..] if !tags.is_empty() => templates::tag_template(template_name, tags, true),
+            _ => {
+                Self::show_usage();
+                return;
+            }
+        };
+
+        match result {
+            Ok(tags) if tags.is_empty() => println!("{} has no tags.", template_name),
+            Ok(tags) => println!("{}: {}", template_name, tags.join(", ")),
+            Err(e) => println!("Error tagging the template: {:?}", e),
+        }
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} tag -n <Template Name> [add|rm <Tag>...]",
+            BIN_NAME
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use crate::commands::{flag_value, Command};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+
+pub struct Update;
+
+impl Command for Update
+{
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        templates::update_templates(flag_value(&flags, "-n"));
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} update [-n <Template Name>]",
+            BIN_NAME
+        );
+    }
+}
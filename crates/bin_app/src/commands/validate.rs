@@ -0,0 +1,59 @@
+use crate::commands::{fail, flag_values, output_format, write_output, Command};
+use crate::templates::report::{self, ValidationReport};
+use crate::{templates, BIN_NAME};
+use std::collections::HashMap;
+use std::io::Write;
+
+pub struct Validate;
+
+impl Command for Validate
+{
+    const REPEATABLE_FLAGS: &[&str] = &["-n"];
+
+    fn execute(flags: HashMap<String, Vec<String>>)
+    {
+        let format = match output_format(&flags) {
+            Ok(format) => format,
+            Err(e) => fail(format!("Error validating the templates: {:?}", e)),
+        };
+
+        let names = flag_values(&flags, "-n");
+        let reports = match names.is_empty() {
+            true => templates::get_available_templates()
+                .iter()
+                .map(|(template_data, name)| ValidationReport::new(Some(template_data), name))
+                .collect::<Vec<_>>(),
+            false => names
+                .iter()
+                .map(|name| ValidationReport::new(templates::get_template_data(name).as_ref(), name))
+                .collect(),
+        };
+
+        if let Some(format) = format {
+            match report::render(&reports, format) {
+                Ok(output) => write_output(|out| writeln!(out, "{}", output)),
+                Err(e) => fail(format!("Error validating the templates: {:?}", e)),
+            }
+            return;
+        }
+
+        write_output(|out| {
+            for report in &reports {
+                match report.valid {
+                    true => writeln!(out, "{}: valid.", report.name)?,
+                    false => writeln!(out, "{}: {}.", report.name, report.problems.join(", "))?,
+                }
+            }
+
+            Ok(())
+        });
+    }
+
+    fn show_usage()
+    {
+        println!(
+            "USAGE: {} validate [-n <Template Name>]... [--format json|yaml|table]",
+            BIN_NAME
+        );
+    }
+}
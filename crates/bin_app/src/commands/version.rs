@@ -5,7 +5,7 @@ pub struct Version;
 
 impl Command for Version
 {
-    fn execute(_flags: HashMap<String, String>)
+    fn execute(_flags: HashMap<String, Vec<String>>)
     {
         println!("Version: {}", env!("CARGO_PKG_VERSION"));
     }
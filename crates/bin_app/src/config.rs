@@ -1,16 +1,52 @@
-
-/*use crate::CONFIG_DIR;
+use crate::CONFIG_DIR;
 use serde::{Deserialize, Serialize};
-use std::cell::LazyCell;
+use std::collections::HashMap;
+use std::fs;
 use std::path::PathBuf;
+use std::sync::LazyLock;
+
+pub static CONFIG_FILE: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("config.toml"));
 
-#[warn(dead_code)]
-const CONFIG_FILE: LazyCell<PathBuf> =
-    LazyCell::new(|| PathBuf::from(CONFIG_DIR.join("config.toml")));
+/// Shorthands available even without a config file. `{}` is replaced by the rest of the source.
+const DEFAULT_SHORTHANDS: [(&str, &str); 3] = [
+    ("gh", "https://github.com/{}.git"),
+    ("gl", "https://gitlab.com/{}.git"),
+    ("bb", "https://bitbucket.org/{}.git"),
+];
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct Config
 {
+    #[serde(default)]
+    shorthands: HashMap<String, String>,
+}
+
+impl Config
+{
+    /// Loads the config file. A missing file means the default config, an invalid one is
+    /// reported and ignored.
+    pub fn load() -> Self
+    {
+        let content = match fs::read_to_string(CONFIG_FILE.as_path()) {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+
+        toml::from_str(content.as_str()).unwrap_or_else(|e| {
+            eprintln!("Ignoring the invalid config file {}: {}", CONFIG_FILE.display(), e);
+            Self::default()
+        })
+    }
+
+    /// Default shorthands overridden and extended by the ones of the config file.
+    pub fn shorthands(&self) -> HashMap<String, String>
+    {
+        let mut shorthands = DEFAULT_SHORTHANDS
+            .iter()
+            .map(|(prefix, url)| (prefix.to_string(), url.to_string()))
+            .collect::<HashMap<String, String>>();
 
+        shorthands.extend(self.shorthands.clone());
+        shorthands
+    }
 }
-*/
\ No newline at end of file
@@ -1,4 +1,4 @@
-use std::cell::LazyCell;
+use std::sync::LazyLock;
 use std::env;
 use std::path::PathBuf;
 
@@ -18,40 +18,38 @@ const BIN_NAME: &str = {
     }
 };
 
-const CONFIG_DIR: LazyCell<PathBuf> = LazyCell::new(|| {
-    let home = env::var("MKT_HOME");
-
-    let path = if let Ok(hom) = home {
-        hom
-    } else {
-        if cfg!(target_os = "windows") {
-            env::var("USERPROFILE").unwrap_or_else(|_| String::from("Home directory not found"))
-                + "/."
-                + BIN_NAME
-        } else {
-            env::var("HOME").unwrap_or_else(|_| String::from("Home directory not found"))
-                + "/."
-                + BIN_NAME
-        }
+static CONFIG_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    // Without a home directory the templates are kept in the temp dir, doctor reports it
+    let path = match mkt_home() {
+        Some(home) => home,
+        None => home_dir().unwrap_or_else(env::temp_dir).join(format!(".{}", BIN_NAME)),
     };
 
-    let path = PathBuf::from(path);
-
-    if !path.exists() {
-        std::fs::create_dir(&path).expect("Should create the config dir.");
+    if let Err(e) = std::fs::create_dir_all(&path) {
+        eprintln!("The config dir {} couldn't be created: {e:?}", path.display());
     }
 
     path
 });
 
+fn mkt_home() -> Option<PathBuf> {
+    env::var_os("MKT_HOME").filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(target_os = "windows") { "USERPROFILE" } else { "HOME" };
+
+    env::var_os(var).filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
 fn main() {
-    let command = env::args().skip(1).collect::<Vec<String>>().join(" ");
-    let command = command.as_str();
+    let args = env::args().skip(1).collect::<Vec<String>>();
 
-    match commands::try_execute(command) {
+    match commands::try_execute(&args) {
         Ok(_) => (),
         Err(err) => {
-            println!("Error: {:?}", err);
+            eprintln!("Error: {:?}", err);
+            std::process::exit(1);
         }
     }
 }
@@ -1,43 +1,124 @@
-mod data;
+pub mod bundle;
+pub mod data;
+mod archive;
 mod dir;
+mod editor;
 mod git;
+pub mod health;
+mod ignore;
+mod manifest;
+mod picker;
+pub mod report;
+pub mod search;
+mod source;
+mod store;
 mod txml;
 
+use crate::templates::archive::ArchiveTemplate;
 use crate::templates::data::TemplateData;
 use crate::templates::dir::DirTemplate;
-use crate::templates::git::GitTemplate;
-use crate::CONFIG_DIR;
-use std::cell::LazyCell;
+use crate::templates::git::{GitTemplate, History};
+use crate::templates::source::SourceType;
+use crate::commands::{flag_value, flag_values, push_flag_value};
+use crate::{BIN_NAME, CONFIG_DIR};
+use std::sync::LazyLock;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::{env, fs, io, process};
+use std::path::{Path, PathBuf};
+use txml_processor::commands::DeferredCommand;
+use txml_processor::txml_elements::Variable;
+use txml_processor::txml_structure::TxmlStructure;
 
-pub const SAVE_TEMPLATES_DIR: LazyCell<PathBuf> = LazyCell::new(|| {
+pub static SAVE_TEMPLATES_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     let path = CONFIG_DIR.join("templates");
 
-    if !path.exists() {
-        fs::create_dir(&path).expect("Should create the templates dir.");
+    if let Err(e) = fs::create_dir_all(&path) {
+        eprintln!("The templates' dir {} couldn't be created: {e:?}", path.display());
     }
 
+    // Nothing else can change the store until it is consistent
+    let _lock = store::lock();
+    store::recover(&path);
+    store::migrate_layout(&path);
+
     path
 });
 
 pub trait Template {
-    fn generate(&self, name: &str, flags: HashMap<String, String>) -> Result<(), TemplateError>;
-    fn save(&self, name: &str, flags: HashMap<String, String>) -> Result<(), TemplateError>;
+    fn generate(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError>;
+
+    /// Same as `generate` but leaving out what reaches beyond the spawned files, e.g. the
+    /// commands of a TXML template, which is returned to be done once the files are confirmed.
+    fn generate_staged(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<Vec<PendingStep>, TemplateError> {
+        self.generate(name, flags).map(|_| Vec::new())
+    }
+
+    fn save(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError>;
     fn remove(&self);
     fn validate(&self) -> bool;
     fn get_description(&self) -> String;
 }
 
+/// Step of a staged spawn left for once its files are confirmed and moved.
+pub enum PendingStep {
+    Command(DeferredCommand),
+    /// The spawned repository and what to do with its history.
+    History(PathBuf, History),
+}
+
+impl PendingStep {
+    /// Top-level entry of the spawn the step belongs to, nothing is done if it wasn't moved.
+    fn entry(&self) -> &Path {
+        match self {
+            PendingStep::Command(command) => command.entry(),
+            PendingStep::History(dir, _) => dir,
+        }
+    }
+
+    /// Does the step as the spawn would have, with `root` as the spawn directory.
+    fn run(&self, root: &Path) -> Result<(), TemplateError> {
+        match self {
+            PendingStep::Command(command) => {
+                if let Err(e) = command.run(root) {
+                    eprintln!("The command {} failed: {e:?}", command.command());
+                }
+
+                Ok(())
+            }
+            PendingStep::History(dir, history) => git::apply_history(&root.join(dir), *history),
+        }
+    }
+}
+
 pub enum TemplateError {
     IoError,
     InvalidTemplate,
     ErrorExecutingGit,
     ErrorConvertingDir2Txml,
     InvalidPath,
+    InvalidTemplatizeValue,
+    InvalidManifest,
+    GitSubdirNotFound,
+    InvalidHistoryMode,
+    GitCommandFailed(String),
+    GitSourceUnreachable,
+    GitMirrorMissing,
+    InvalidSourceType,
+    UnknownTemplateClass(String),
+    InvalidTemplateName(String),
+    TemplateNotFound(String),
+    TemplateAlreadyExists(String),
+    EditorNotSet,
+    EditorFailed(String),
+    NotEditable(String),
+    InvalidBundle(String),
+    InvalidRenameValue,
+    InvalidArchive,
+    InvalidSortOrder,
+    InvalidOutputFormat,
+    InvalidVariableValue,
+    InvalidTag(String),
 }
 
 impl Debug for TemplateError {
@@ -49,6 +130,56 @@ impl Debug for TemplateError {
                 write!(f, "Error executing git. Check if it is installed.")
             }
             TemplateError::InvalidPath => write!(f, "Invalid path."),
+            TemplateError::InvalidManifest => write!(f, "Invalid mkt.toml manifest."),
+            TemplateError::GitSubdirNotFound => {
+                write!(f, "The subdirectory doesn't exist in the repository.")
+            }
+            TemplateError::GitCommandFailed(stderr) => write!(f, "git failed: {}", stderr),
+            TemplateError::GitSourceUnreachable => {
+                write!(f, "The git repository doesn't exist or isn't reachable.")
+            }
+            TemplateError::GitMirrorMissing => {
+                write!(f, "The git template isn't cached. Run update or spawn it without --offline.")
+            }
+            TemplateError::UnknownTemplateClass(class) => {
+                write!(f, "Unknown template class {}. It may need a newer version.", class)
+            }
+            TemplateError::InvalidTemplateName(name) => write!(f, "{} is not a valid template name.", name),
+            TemplateError::TemplateNotFound(name) => write!(f, "Template {} not found.", name),
+            TemplateError::TemplateAlreadyExists(name) => {
+                write!(f, "Template {} already exists.", name)
+            }
+            TemplateError::EditorNotSet => write!(f, "Set $VISUAL or $EDITOR to the editor to use."),
+            TemplateError::EditorFailed(editor) => write!(f, "The editor {} failed.", editor),
+            TemplateError::NotEditable(class) => {
+                write!(f, "{} templates can't be edited, edit their source instead.", class)
+            }
+            TemplateError::InvalidBundle(reason) => write!(f, "Invalid bundle: {}.", reason),
+            TemplateError::InvalidRenameValue => {
+                write!(f, "Invalid --rename value. Use <Name in the bundle>=<New name>.")
+            }
+            TemplateError::InvalidArchive => write!(f, "The archive isn't a valid zip, tar or tar.gz file."),
+            TemplateError::InvalidSortOrder => {
+                write!(f, "Invalid --sort value. Use name, created, updated or spawned.")
+            }
+            TemplateError::InvalidOutputFormat => {
+                write!(f, "Invalid --format value. Use json, yaml or table.")
+            }
+            TemplateError::InvalidVariableValue => {
+                write!(f, "Invalid --var value. Use <Variable>=<Value>.")
+            }
+            TemplateError::InvalidTag(tag) => {
+                write!(f, "{} is not a valid tag. Tags can't be empty or have spaces or commas.", tag)
+            }
+            TemplateError::InvalidSourceType => {
+                write!(f, "Invalid --type value. Use git, dir, txml or archive.")
+            }
+            TemplateError::InvalidHistoryMode => {
+                write!(f, "Invalid --history value. Use keep, strip or fresh.")
+            }
+            TemplateError::InvalidTemplatizeValue => {
+                write!(f, "Invalid --templatize value. Use <literal>=<variable>.")
+            }
             TemplateError::ErrorConvertingDir2Txml => {
                 write!(f, "Error converting directory to txml.")
             }
@@ -56,10 +187,13 @@ impl Debug for TemplateError {
     }
 }
 
-pub fn add_template(name: &str, path: &str, flags: HashMap<String, String>) {
-    remove_template(name);
+pub fn add_template(name: &str, path: &str, flags: HashMap<String, Vec<String>>) {
+    if !is_valid_template_name(name) {
+        println!("Error adding the template: {} is not a valid template name.", name);
+        return;
+    }
 
-    let template = build_template(path);
+    let template = build_template(path, flag_value(&flags, "--type"));
     let template = match template {
         Ok(template) => template,
         Err(e) => {
@@ -68,95 +202,147 @@ pub fn add_template(name: &str, path: &str, flags: HashMap<String, String>) {
         }
     };
 
-    template
-        .save(name, flags)
-        .expect("Should save the template.");
-}
+    let _lock = match store::lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("Error adding the template: {:?}", e);
+            return;
+        }
+    };
 
-fn build_template(path: &str) -> Result<Box<dyn Template>, TemplateError> {
-    if path.ends_with(".git") {
-        return Ok(Box::new(GitTemplate::new(path)));
+    // Checked again under the lock, another process may have added it since the command started
+    if !flags.contains_key("-r") && get_template_data_path(name).is_some() {
+        println!("That template name is already being used. Use -r to replace it.");
+        return;
     }
 
-    let path = PathBuf::from(path);
+    // The new template is saved aside and only replaces the current one once it is complete
+    let staging = store::new_name(name);
+    let previous = get_template_data(name);
+
+    let result = store::discard(&staging)
+        .map_err(|_e| TemplateError::IoError)
+        .and_then(|_| template.save(&staging, flags))
+        .and_then(|_| complete_template_data(&staging, name, path))
+        .and_then(|_| store::replace(&staging, name).map_err(|_e| TemplateError::IoError));
+
+    if let Err(e) = result {
+        println!("Error adding the template: {:?}", e);
 
-    if !path.exists() {
-        return Err(TemplateError::InvalidPath);
+        if let Err(e) = store::discard(&staging) {
+            println!("The incomplete template couldn't be removed: {:?}", e);
+        }
+
+        return;
     }
 
-    if path.is_dir() {
-        return Ok(Box::new(DirTemplate::new(path)));
+    if let Some(previous) = previous {
+        remove_replaced(&previous, name);
     }
+}
 
-    if path.is_file() {
-        let local_txml_template = txml::TxmlTemplate::new(path);
+/// Cleans up what a replaced template kept outside its directory, e.g. a git mirror or the
+/// payload of an older version. What was inside went away with the directory.
+fn remove_replaced(previous: &TemplateData, name: &str) {
+    if previous.class() != git::GIT_TEMPLATE && previous.payload_path().starts_with(store::template_dir(name)) {
+        return;
+    }
 
-        if local_txml_template.validate() {
-            return Ok(Box::new(local_txml_template));
-        }
+    if let Ok(previous) = previous.to_template() {
+        previous.remove();
     }
+}
+
+/// The templates only write the data they know about, the rest is common to all of them.
+fn complete_template_data(staging: &str, name: &str, path: &str) -> Result<(), TemplateError> {
+    let mut template_data = TemplateData::load(staging).map_err(|_e| TemplateError::IoError)?;
+
+    template_data.set_source(Some(path.to_string()));
+    template_data.update_checksum();
+    template_data.rebase(staging, name);
 
-    Err(TemplateError::InvalidTemplate)
+    template_data.save(staging).map_err(|_e| TemplateError::IoError)
 }
 
-pub fn remove_template(name: &str) {
-    let template_path = get_template_data_path(name);
+fn build_template(path: &str, source_type: Option<&str>) -> Result<Box<dyn Template>, TemplateError> {
+    let source_type = source_type.map(str::parse::<SourceType>).transpose()?;
+    let source = source::resolve(path, source_type)?;
+
+    match source.source_type {
+        SourceType::Git => Ok(Box::new(GitTemplate::new(&source.location))),
+        SourceType::Dir => Ok(Box::new(DirTemplate::new(PathBuf::from(source.location)))),
+        SourceType::Archive => Ok(Box::new(ArchiveTemplate::new(PathBuf::from(source.location)))),
+        SourceType::Txml => {
+            let local_txml_template = txml::TxmlTemplate::new(PathBuf::from(source.location));
+
+            if !local_txml_template.validate() {
+                return Err(TemplateError::InvalidTemplate);
+            }
 
-    if let None = template_path {
+            Ok(Box::new(local_txml_template))
+        }
+    }
+}
+
+pub fn remove_template(name: &str) {
+    if get_template_data_path(name).is_none() {
         return;
     }
 
-    let template_path = template_path.unwrap();
+    let _lock = match store::lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("Error removing the template: {:?}", e);
+            return;
+        }
+    };
 
-    if let Ok(template_data) =
-        TemplateData::from_json(fs::read_to_string(&template_path).unwrap().as_str())
-    {
-        let template = template_data.to_template();
-        let template = template.as_ref();
+    let template = TemplateData::load(name)
+        .map_err(|_e| TemplateError::IoError)
+        .and_then(|template_data| template_data.to_template());
 
-        template.remove();
-        fs::remove_file(template_path).expect("Should remove the template.");
-    } else {
-        fs::remove_file(template_path).expect("Should remove the template.");
-        println!("Template removed but data wasn't parseable. Any related files were not removed.");
+    if let Err(e) = store::discard(name) {
+        println!("Error removing the template: {:?}", e);
+        return;
+    }
+
+    // Once the template is gone, what it kept outside its directory can be cleaned up
+    match template {
+        Ok(template) => template.remove(),
+        Err(_) => println!("Template removed but data wasn't parseable. Files stored outside its directory were not removed."),
     }
 }
 
 pub fn get_available_templates() -> Vec<(TemplateData, String)> {
-    fs::read_dir(SAVE_TEMPLATES_DIR.as_path())
-        .expect("Should exists")
+    let entries = match fs::read_dir(SAVE_TEMPLATES_DIR.as_path()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("The templates' dir couldn't be read: {e:?}");
+            return Vec::new();
+        }
+    };
+
+    // Broken entries are skipped, doctor reports them
+    let mut templates = entries
+        .flatten()
         .filter_map(|entry| {
-            let entry = entry.as_ref().expect("Should be a dir entry");
-            let path = entry.path();
-            let path = path.as_path();
+            let name = entry.file_name().into_string().ok()?;
 
-            if !path.is_file() {
+            // Hidden directories are templates being moved to the new layout
+            if name.starts_with('.') || !store::data_file(&name).is_file() {
                 return None;
             }
 
-            let name = path
-                .file_name()
-                .expect("Should have a name")
-                .to_str()
-                .expect("Should be a string")
-                .to_string();
-
-            let data = fs::read_to_string(path)
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "Error reading the file."));
-            let data = data.expect("Should read the file.");
-
-            let template_data = TemplateData::from_json(data.as_str());
-
-            match template_data {
-                Ok(data) => Some((data, name)),
-                Err(_) => None,
-            }
+            TemplateData::load(&name).ok().map(|data| (data, name))
         })
-        .collect()
+        .collect::<Vec<_>>();
+
+    templates.sort_by(|(_, a), (_, b)| a.cmp(b));
+    templates
 }
 
 pub fn get_template_data_path(name: &str) -> Option<PathBuf> {
-    let path = PathBuf::from(SAVE_TEMPLATES_DIR.as_path().join(name));
+    let path = store::data_file(name);
     if path.exists() {
         Some(path)
     } else {
@@ -165,32 +351,441 @@ pub fn get_template_data_path(name: &str) -> Option<PathBuf> {
 }
 
 pub fn get_template_data(name: &str) -> Option<TemplateData> {
-    if let Ok(template_data) = TemplateData::load(name) {
-        Some(template_data)
-    } else {
-        None
+    TemplateData::load(name).ok()
+}
+
+/// Spawns the template. Its messages go to the stderr, the stdout may carry the archive.
+pub fn generate(name: &str, output_name: &str, flags: HashMap<String, Vec<String>>) {
+    let template_data = match get_template_data(name) {
+        Some(template_data) => template_data,
+        None => {
+            eprintln!("Template {} not found.", name);
+            return;
+        }
+    };
+
+    let generated = template_data.to_template().and_then(|template| {
+        let generate = || template.generate(output_name, flags.clone());
+
+        match flag_value(&flags, "--archive") {
+            Some(output) => archive::spawn_to_archive(output, generate).map(|_| true),
+            None if flags.contains_key("--confirm") => {
+                spawn_staged(|| template.generate_staged(output_name, flags.clone()), spawn_confirmed)
+            }
+            None => generate().map(|_| true),
+        }
+    });
+
+    match generated {
+        Ok(true) => {}
+        Ok(false) => {
+            eprintln!("Spawn of {} cancelled, nothing was written.", name);
+            return;
+        }
+        Err(e) => {
+            eprintln!("Error generating the template: {:?}", e);
+            return;
+        }
+    }
+
+    let recorded = store::lock().map_err(|e| io::Error::other(format!("{:?}", e))).and_then(|_lock| {
+        // Reloaded under the lock, another process may have changed it while spawning
+        let mut template_data = TemplateData::load(name)?;
+        template_data.mark_spawned();
+        template_data.save(name)
+    });
+
+    if let Err(e) = recorded {
+        eprintln!("The spawn of {} couldn't be recorded: {:?}", name, e);
     }
 }
 
-pub fn generate(name: &str, output_name: &str, flags: HashMap<String, String>) {
-    let template_data = get_template_data(name);
+/// Lets the user pick the template to spawn and the values of its variables, then spawns it
+/// once the planned files are confirmed.
+pub fn spawn_interactive(mut flags: HashMap<String, Vec<String>>) {
+    let templates = get_available_templates();
 
-    if let None = template_data {
-        println!("Template {} not found.", name);
+    if templates.is_empty() {
+        println!("There are no templates to spawn. Add one with {} add.", BIN_NAME);
         return;
     }
 
-    match template_data
-        .unwrap()
-        .to_template()
-        .generate(output_name, flags)
-    {
-        Err(TemplateError::ErrorExecutingGit) => {
-            println!("Error executing git. Check if it is installed.")
+    let (template_data, name) = match picker::pick_template(templates) {
+        Some(picked) => picked,
+        None => return,
+    };
+
+    let given = match variable_values(&flags) {
+        Ok(given) => given,
+        Err(e) => {
+            println!("Error generating the template: {:?}", e);
+            return;
         }
-        Err(_) => println!("Error generating the template."),
-        _ => (),
+    };
+
+    for (variable, value) in picker::ask_variables(&declared_variables(&template_data), &given) {
+        push_flag_value(&mut flags, "--var", &format!("{}={}", variable, value));
+    }
+
+    push_flag_value(&mut flags, "--confirm", "");
+
+    let output_name = flag_value(&flags, "-o").map(String::from).unwrap_or_else(|| name.clone());
+
+    generate(&name, &output_name, flags);
+}
+
+/// Runs `generate` in an empty staging directory instead of the current one and hands it, with
+/// what `generate` returned, to `then`, which moves or packs what was spawned. The staging
+/// directory is always removed.
+fn spawn_staged<G, T>(
+    generate: impl FnOnce() -> Result<G, TemplateError>,
+    then: impl FnOnce(&Path, G) -> Result<T, TemplateError>,
+) -> Result<T, TemplateError> {
+    let staging = env::temp_dir().join(format!("{}-spawn-{}", BIN_NAME, process::id()));
+
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|_e| TemplateError::IoError)?;
     }
+
+    fs::create_dir_all(&staging).map_err(|_e| TemplateError::IoError)?;
+
+    // Templates spawn in the current directory, which is restored even if the spawn fails
+    let current_dir = env::current_dir().map_err(|_e| TemplateError::IoError)?;
+    let result = env::set_current_dir(&staging)
+        .map_err(|_e| TemplateError::IoError)
+        .and_then(|_| {
+            let generated = generate();
+            env::set_current_dir(&current_dir).map_err(|_e| TemplateError::IoError)?;
+            generated
+        })
+        .and_then(|generated| then(&staging, generated));
+
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        eprintln!("{} couldn't be removed: {e:?}", staging.display());
+    }
+
+    result
+}
+
+/// Shows what was spawned in the staging directory and moves it to the current one if the user
+/// confirms it, then does the pending steps of the moved entries. Entries that already exist are
+/// kept. Returns if it was moved.
+fn spawn_confirmed(staging: &Path, steps: Vec<PendingStep>) -> Result<bool, TemplateError> {
+    let current_dir = env::current_dir().map_err(|_e| TemplateError::IoError)?;
+
+    if !picker::confirm_spawn(staging, &current_dir) {
+        return Ok(false);
+    }
+
+    let entries = fs::read_dir(staging).and_then(|entries| entries.collect::<io::Result<Vec<_>>>());
+    let mut kept = Vec::new();
+
+    for entry in entries.map_err(|_e| TemplateError::IoError)? {
+        let dst = current_dir.join(entry.file_name());
+
+        if dst.symlink_metadata().is_ok() {
+            println!("{} already exists. Skipping it.", dst.display());
+            kept.push(PathBuf::from(entry.file_name()));
+            continue;
+        }
+
+        // The staging directory may be in another file system. A symlink is recreated, copying
+        // it would copy what it points to
+        let moved = fs::rename(entry.path(), &dst).or_else(|_| {
+            let file_type = entry.file_type()?;
+
+            if file_type.is_symlink() {
+                dir::copy_symlink(&entry.path(), &dst, None)
+            } else if file_type.is_dir() {
+                dir::copy_dir_all(&entry.path(), &dst, &|_| true, None)
+            } else {
+                fs::copy(entry.path(), &dst).map(|_| ())
+            }
+        });
+
+        moved.map_err(|_e| TemplateError::IoError)?;
+    }
+
+    for step in steps.iter().filter(|step| !kept.iter().any(|entry| step.entry() == entry)) {
+        step.run(&current_dir)?;
+    }
+
+    Ok(true)
+}
+
+/// Variables declared by a TXML or directory template, with their default values. Those of git
+/// templates are only known once they are cloned.
+pub fn declared_variables(template_data: &TemplateData) -> Vec<Variable> {
+    let variables = match template_data.class() {
+        txml::TXML_TEMPLATE => fs::read_to_string(template_data.payload_path())
+            .ok()
+            .and_then(|txml| TxmlStructure::obtain_variables(&txml).ok()),
+        dir::DIR_TEMPLATE => manifest::Manifest::load(&template_data.payload_path())
+            .ok()
+            .flatten()
+            .map(|manifest| manifest.variables()),
+        _ => None,
+    };
+
+    variables.unwrap_or_default()
+}
+
+/// Parses the `--var <Variable>=<Value>` values, used instead of asking for the variables.
+fn variable_values(flags: &HashMap<String, Vec<String>>) -> Result<HashMap<String, String>, TemplateError> {
+    flag_values(flags, "--var")
+        .into_iter()
+        .map(|value| match value.split_once('=') {
+            Some((variable, value)) if !variable.is_empty() => Ok((variable.to_string(), value.to_string())),
+            _ => Err(TemplateError::InvalidVariableValue),
+        })
+        .collect()
+}
+
+/// Rewrites the absolute payload paths saved by older versions relative to the templates' dir.
+pub fn migrate_paths() {
+    let _lock = match store::lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("Error migrating the templates: {:?}", e);
+            return;
+        }
+    };
+
+    let mut migrated = 0;
+
+    for (mut template_data, name) in get_available_templates() {
+        let old_path = template_data.data_path().to_string();
+
+        if !template_data.relativize() {
+            continue;
+        }
+
+        match template_data.save(&name) {
+            Ok(_) => {
+                println!("{}: {} -> {}", name, old_path, template_data.data_path());
+                migrated += 1;
+            }
+            Err(e) => println!("Error migrating {}: {:?}", name, e),
+        }
+    }
+
+    println!("{} template(s) migrated.", migrated);
+}
+
+/// Renames a stored template, its payload included.
+pub fn move_template(old: &str, new: &str) {
+    let result = prepare_transfer(old, new).and_then(|(_lock, mut template_data)| {
+        fs::rename(store::template_dir(old), store::template_dir(new)).map_err(|_e| TemplateError::IoError)?;
+        template_data.rebase(old, new);
+
+        // The data still points to the old directory until it is saved
+        template_data.save(new).map_err(|_e| {
+            let _ = fs::rename(store::template_dir(new), store::template_dir(old));
+            TemplateError::IoError
+        })
+    });
+
+    match result {
+        Ok(_) => println!("Template {} renamed to {}.", old, new),
+        Err(e) => println!("Error renaming the template: {:?}", e),
+    }
+}
+
+/// Duplicates a stored template, its payload included, under a new name.
+pub fn copy_template(src: &str, dst: &str) {
+    let result = prepare_transfer(src, dst).and_then(|(_lock, mut template_data)| {
+        // The copy is saved aside and only appears once it is complete
+        let staging = store::new_name(dst);
+
+        let copied = store::discard(&staging)
+            .and_then(|_| dir::copy_dir_all(&store::template_dir(src), &store::template_dir(&staging), &|_| true, None))
+            .and_then(|_| {
+                template_data.rebase(src, dst);
+                template_data.save(&staging)
+            })
+            .and_then(|_| store::replace(&staging, dst));
+
+        copied.map_err(|_e| {
+            let _ = store::discard(&staging);
+            TemplateError::IoError
+        })
+    });
+
+    match result {
+        Ok(_) => println!("Template {} copied to {}.", src, dst),
+        Err(e) => println!("Error copying the template: {:?}", e),
+    }
+}
+
+/// Locks the store and loads the template to move or copy, once both names are checked.
+fn prepare_transfer(src: &str, dst: &str) -> Result<(store::StoreLock, TemplateData), TemplateError> {
+    if !is_valid_template_name(dst) {
+        return Err(TemplateError::InvalidTemplateName(dst.to_string()));
+    }
+
+    let lock = store::lock()?;
+
+    if get_template_data_path(src).is_none() {
+        return Err(TemplateError::TemplateNotFound(src.to_string()));
+    }
+
+    if store::template_dir(dst).exists() {
+        return Err(TemplateError::TemplateAlreadyExists(dst.to_string()));
+    }
+
+    let template_data = TemplateData::load(src).map_err(|_e| TemplateError::IoError)?;
+
+    Ok((lock, template_data))
+}
+
+/// Opens the payload of a template in the user's editor and records the changes.
+pub fn edit_template(name: &str) {
+    let template_data = match get_template_data(name) {
+        Some(template_data) => template_data,
+        None => {
+            println!("Template {} not found.", name);
+            return;
+        }
+    };
+
+    let edited = match template_data.class() {
+        txml::TXML_TEMPLATE => edit_txml(name, &template_data),
+        dir::DIR_TEMPLATE => editor::open(&template_data.payload_path()).map(|_| true),
+        class => Err(TemplateError::NotEditable(class.to_string())),
+    };
+
+    match edited {
+        Ok(true) => {}
+        Ok(false) => {
+            println!("Template {} not changed.", name);
+            return;
+        }
+        Err(e) => {
+            println!("Error editing the template: {:?}", e);
+            return;
+        }
+    }
+
+    let recorded = store::lock().map_err(|e| io::Error::other(format!("{:?}", e))).and_then(|_lock| {
+        let mut template_data = TemplateData::load(name)?;
+        template_data.update_checksum();
+        template_data.touch();
+        template_data.save(name)
+    });
+
+    match recorded {
+        Ok(_) => println!("Template {} saved.", name),
+        Err(e) => println!("The changes to {} couldn't be recorded: {:?}", name, e),
+    }
+}
+
+/// Edits a copy of the TXML, which only replaces the stored one once it is valid, so the store
+/// never keeps a broken template. Returns whether it changed.
+fn edit_txml(name: &str, template_data: &TemplateData) -> Result<bool, TemplateError> {
+    let payload = template_data.payload_path();
+    let original = fs::read_to_string(&payload).map_err(|_e| TemplateError::IoError)?;
+
+    // The copy keeps the extension, so the editor highlights it
+    let copy = env::temp_dir().join(format!("{}-{}-{}.txml", crate::BIN_NAME, name, process::id()));
+    fs::write(&copy, &original).map_err(|_e| TemplateError::IoError)?;
+
+    let edited = loop {
+        if let Err(e) = editor::open(&copy) {
+            break Err(e);
+        }
+
+        if TxmlStructure::validate_txml_file(&copy) {
+            break fs::read_to_string(&copy).map(Some).map_err(|_e| TemplateError::IoError);
+        }
+
+        println!("The TXML of {} isn't valid.", name);
+
+        if !editor::ask_reopen() {
+            println!("Changes reverted.");
+            break Ok(None);
+        }
+    };
+
+    let _ = fs::remove_file(&copy);
+
+    let edited = match edited? {
+        Some(edited) if edited != original => edited,
+        _ => return Ok(false),
+    };
+
+    let _lock = store::lock()?;
+    store::write_atomic(&payload, edited.as_bytes()).map_err(|_e| TemplateError::IoError)?;
+
+    Ok(true)
+}
+
+/// Fetches the new commits of a git template, or of every git template if no name is given.
+pub fn update_templates(name: Option<&str>) {
+    let templates = match name {
+        Some(name) => match get_template_data(name) {
+            Some(template_data) => vec![(template_data, name.to_string())],
+            None => {
+                println!("Template {} not found.", name);
+                return;
+            }
+        },
+        None => get_available_templates(),
+    };
+
+    for (template_data, name) in templates {
+        if template_data.class() != git::GIT_TEMPLATE {
+            continue;
+        }
+
+        let git_template = GitTemplate::from_data(&template_data);
+
+        if let Err(e) = git_template.update_mirror() {
+            println!("Error updating {}: {:?}", name, e);
+            continue;
+        }
+
+        let description = git_template.read_description();
+        let saved = store::lock().map_err(|e| io::Error::other(format!("{:?}", e))).and_then(|_lock| {
+            let mut template_data = TemplateData::load(&name)?;
+            template_data.set_description(description);
+            template_data.touch();
+            template_data.save(&name)
+        });
+
+        match saved {
+            Ok(_) => println!("Updated {}.", name),
+            Err(e) => println!("Error updating {}: {:?}", name, e),
+        }
+    }
+}
+
+/// Adds the tags to a template, or removes them. Returns the tags the template ends up with.
+pub fn tag_template(name: &str, tags: &[&str], remove: bool) -> Result<Vec<String>, TemplateError> {
+    let tags = tags.iter().map(|tag| search::normalize_tag(tag)).collect::<Result<Vec<_>, _>>()?;
+
+    let _lock = store::lock()?;
+    let mut template_data = get_template_data(name).ok_or_else(|| TemplateError::TemplateNotFound(name.to_string()))?;
+    let mut template_tags = template_data.tags().to_vec();
+
+    if remove {
+        template_tags.retain(|tag| !tags.contains(tag));
+    } else {
+        for tag in tags {
+            if !template_tags.contains(&tag) {
+                template_tags.push(tag);
+            }
+        }
+    }
+
+    template_data.set_tags(template_tags.clone());
+    template_data.save(name).map_err(|_e| TemplateError::IoError)?;
+
+    Ok(template_tags)
+}
+
+/// Every template gets a directory named after it, hidden ones are reserved for the store.
+fn is_valid_template_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && is_valid_name(name)
 }
 
 fn is_valid_name(name: &str) -> bool {
@@ -0,0 +1,312 @@
+use crate::templates::dir::DirTemplate;
+use crate::templates::{spawn_staged, PendingStep, Template, TemplateError};
+use crate::BIN_NAME;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::{env, process};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Folders added by archivers next to the content, ignored when looking for a single root.
+const ARCHIVER_FOLDERS: [&str; 1] = ["__MACOSX"];
+
+/// Archive path that stands for the stdout, which always gets a tar.
+pub const STDOUT_ARCHIVE: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format of an archive, told by its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+
+        if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// A zip or tar archive of a directory. It is extracted to a temporary directory and handled
+/// as a dir template from there, so it is saved as a dir or TXML template.
+pub struct ArchiveTemplate {
+    archive: PathBuf,
+}
+
+impl ArchiveTemplate {
+    pub fn new(archive: PathBuf) -> Self {
+        Self { archive }
+    }
+
+    /// Runs `action` on the extracted archive, which is removed afterwards. A single top-level
+    /// folder is skipped unless `--keep-root` is set.
+    fn with_extracted<T>(
+        &self,
+        flags: &HashMap<String, Vec<String>>,
+        action: impl FnOnce(DirTemplate) -> Result<T, TemplateError>,
+    ) -> Result<T, TemplateError> {
+        let format = ArchiveFormat::from_path(&self.archive).ok_or(TemplateError::InvalidArchive)?;
+        let temp_dir = env::temp_dir().join(format!("{}-archive-{}", BIN_NAME, process::id()));
+        // Named after the archive, it is the root directory of the template when it is kept
+        let extracted = temp_dir.join(archive_stem(&self.archive));
+
+        if temp_dir.exists() {
+            fs::remove_dir_all(&temp_dir).map_err(|_e| TemplateError::IoError)?;
+        }
+
+        let result = extract(&self.archive, format, &extracted).and_then(|_| {
+            let root = match flags.contains_key("--keep-root") {
+                true => extracted.clone(),
+                false => single_root(&extracted).unwrap_or(extracted.clone()),
+            };
+
+            action(DirTemplate::new(root))
+        });
+
+        if let Err(e) = fs::remove_dir_all(&temp_dir) {
+            eprintln!("{} couldn't be removed: {e:?}", temp_dir.display());
+        }
+
+        result
+    }
+}
+
+impl Template for ArchiveTemplate {
+    fn generate(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        self.with_extracted(&flags.clone(), |template| template.generate(name, flags))
+    }
+
+    fn generate_staged(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<Vec<PendingStep>, TemplateError> {
+        self.with_extracted(&flags.clone(), |template| template.generate_staged(name, flags))
+    }
+
+    fn save(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        self.with_extracted(&flags.clone(), |template| template.save(name, flags))
+    }
+
+    /// Archives are never stored, they are saved as dir or TXML templates.
+    fn remove(&self) {}
+
+    fn validate(&self) -> bool {
+        ArchiveFormat::from_path(&self.archive).is_some() && self.archive.is_file()
+    }
+
+    fn get_description(&self) -> String {
+        "Archive Template".to_string()
+    }
+}
+
+/// Name of the archive without its extension, e.g. `starter` for `starter.tar.gz`.
+fn archive_stem(archive: &Path) -> String {
+    let file_name = archive.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = [".tar.gz", ".tgz", ".tar", ".zip"]
+        .iter()
+        .find(|extension| {
+            file_name
+                .get(file_name.len().saturating_sub(extension.len())..)
+                .is_some_and(|end| end.eq_ignore_ascii_case(extension))
+        })
+        .map_or(0, |extension| extension.len());
+    let stem = &file_name[..file_name.len() - extension];
+
+    match stem.is_empty() {
+        true => "archive".to_string(),
+        false => stem.to_string(),
+    }
+}
+
+/// Extracts the archive in `dst`. Entries outside of it, e.g. `../file`, are never written.
+fn extract(archive: &Path, format: ArchiveFormat, dst: &Path) -> Result<(), TemplateError> {
+    let file = File::open(archive).map_err(|_e| TemplateError::InvalidPath)?;
+
+    fs::create_dir_all(dst).map_err(|_e| TemplateError::IoError)?;
+
+    match format {
+        ArchiveFormat::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut zip| zip.extract(dst))
+            .map_err(|_e| TemplateError::InvalidArchive),
+        ArchiveFormat::Tar => extract_tar(file, dst),
+        ArchiveFormat::TarGz => extract_tar(GzDecoder::new(file), dst),
+    }
+}
+
+fn extract_tar(reader: impl Read, dst: &Path) -> Result<(), TemplateError> {
+    tar::Archive::new(reader).unpack(dst).map_err(|_e| TemplateError::InvalidArchive)
+}
+
+/// Runs `generate` in an empty staging directory and packs what it creates in the archive
+/// instead of leaving it in the current directory.
+pub fn spawn_to_archive(
+    output: &str,
+    generate: impl FnOnce() -> Result<(), TemplateError>,
+) -> Result<(), TemplateError> {
+    let format = match output {
+        STDOUT_ARCHIVE => ArchiveFormat::Tar,
+        _ => ArchiveFormat::from_path(Path::new(output)).ok_or(TemplateError::InvalidArchive)?,
+    };
+
+    spawn_staged(generate, |staging, _| write_archive(staging, output, format))
+}
+
+/// Packs the content of `src` in the archive. Files are written aside first, so a failed
+/// spawn never leaves half an archive behind.
+fn write_archive(src: &Path, output: &str, format: ArchiveFormat) -> Result<(), TemplateError> {
+    if output == STDOUT_ARCHIVE {
+        let mut stdout = io::stdout().lock();
+        write_tar(src, &mut stdout).and_then(|_| stdout.flush()).map_err(|_e| TemplateError::IoError)?;
+        return Ok(());
+    }
+
+    let output = Path::new(output);
+    let file_name = output.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let partial = output.with_file_name(format!(".{}.partial", file_name));
+
+    let written = File::create(&partial).and_then(|file| match format {
+        ArchiveFormat::Zip => write_zip(src, file).map_err(io::Error::other),
+        ArchiveFormat::Tar => write_tar(src, file),
+        ArchiveFormat::TarGz => {
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            write_tar(src, &mut encoder)?;
+            encoder.finish().map(|_| ())
+        }
+    });
+
+    match written.and_then(|_| fs::rename(&partial, output)) {
+        Ok(_) => Ok(()),
+        Err(_e) => {
+            let _ = fs::remove_file(&partial);
+            Err(TemplateError::IoError)
+        }
+    }
+}
+
+fn write_tar(src: &Path, writer: impl Write) -> io::Result<()> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    for entry in sorted_entries(src)? {
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            builder.append_dir_all(entry.file_name(), &path)?;
+        } else {
+            builder.append_path_with_name(&path, entry.file_name())?;
+        }
+    }
+
+    builder.into_inner().map(|_| ())
+}
+
+fn write_zip(src: &Path, file: File) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(file);
+
+    add_to_zip(&mut zip, src, "")?;
+    zip.finish().map(|_| ())
+}
+
+fn add_to_zip(zip: &mut ZipWriter<File>, dir: &Path, prefix: &str) -> zip::result::ZipResult<()> {
+    for entry in sorted_entries(dir)? {
+        let path = entry.path();
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        let metadata = fs::symlink_metadata(&path)?;
+        let options = SimpleFileOptions::default().unix_permissions(permissions(&metadata));
+
+        if metadata.file_type().is_symlink() {
+            zip.add_symlink(name, fs::read_link(&path)?.to_string_lossy(), options)?;
+        } else if metadata.is_dir() {
+            zip.add_directory(format!("{}/", name), options)?;
+            add_to_zip(zip, &path, &format!("{}/", name))?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(&path)?, zip)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Entries of a directory by name, so the same spawn always packs the same archive.
+fn sorted_entries(dir: &Path) -> io::Result<Vec<fs::DirEntry>> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    Ok(entries)
+}
+
+#[cfg(unix)]
+fn permissions(metadata: &fs::Metadata) -> u32 {
+    std::os::unix::fs::PermissionsExt::mode(&metadata.permissions())
+}
+
+#[cfg(not(unix))]
+fn permissions(metadata: &fs::Metadata) -> u32 {
+    match metadata.permissions().readonly() {
+        true => 0o444,
+        false => 0o644,
+    }
+}
+
+/// The only folder at the top of an extracted archive, as in `starter-main/...`.
+fn single_root(dir: &Path) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| !ARCHIVER_FOLDERS.iter().any(|folder| entry.file_name() == *folder))
+        .collect::<Vec<_>>();
+
+    match &entries[..] {
+        [entry] if entry.path().is_dir() => Some(entry.path()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_format() {
+        assert_eq!(ArchiveFormat::from_path(Path::new("starter.zip")), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_path(Path::new("starter.TAR.GZ")), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path(Path::new("starter.tgz")), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path(Path::new("starter.tar")), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::from_path(Path::new("starter.xml")), None);
+    }
+
+    #[test]
+    fn test_archive_stem() {
+        assert_eq!(archive_stem(Path::new("dir/starter.TAR.GZ")), "starter");
+        assert_eq!(archive_stem(Path::new("starter.v2.zip")), "starter.v2");
+        assert_eq!(archive_stem(Path::new(".tar")), "archive");
+    }
+
+    #[test]
+    fn test_single_root() {
+        let dir = env::temp_dir().join(format!("mkt-archive-test-{}", process::id()));
+        fs::create_dir_all(dir.join("starter-main/src")).unwrap();
+        fs::create_dir_all(dir.join("__MACOSX")).unwrap();
+
+        assert_eq!(single_root(&dir), Some(dir.join("starter-main")));
+
+        fs::write(dir.join("README.md"), "").unwrap();
+
+        assert_eq!(single_root(&dir), None);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
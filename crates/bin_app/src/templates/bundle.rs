@@ -0,0 +1,258 @@
+use crate::templates::data::TemplateData;
+use crate::templates::dir::DIR_TEMPLATE;
+use crate::templates::txml::TXML_TEMPLATE;
+use crate::templates::{get_template_data, is_valid_template_name, remove_replaced, store, TemplateError};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+use tar::{Archive, Builder, Header};
+
+/// Manifest of a bundle, next to one directory per template laid out as in the store:
+/// `<name>/template.json` and `<name>/payload`.
+const BUNDLE_MANIFEST: &str = "bundle.json";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct BundleManifest {
+    version: u32,
+    templates: Vec<String>,
+}
+
+/// Packs the data and payload of the templates in a tar.gz bundle. Git templates only take
+/// their data, they are cloned again from their url.
+pub fn export(names: &[&str], output: &Path) -> Result<(), TemplateError> {
+    let _lock = store::lock()?;
+
+    for name in names {
+        if get_template_data(name).is_none() {
+            return Err(TemplateError::TemplateNotFound(name.to_string()));
+        }
+    }
+
+    // Written aside, so a failed export never leaves half a bundle behind
+    let file_name = output.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let partial = output.with_file_name(format!(".{}.partial", file_name));
+
+    let result = File::create(&partial)
+        .map_err(|_e| TemplateError::IoError)
+        .and_then(|file| write_bundle(names, file))
+        .and_then(|_| fs::rename(&partial, output).map_err(|_e| TemplateError::IoError));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+
+    result
+}
+
+fn write_bundle(names: &[&str], file: File) -> Result<(), TemplateError> {
+    let mut builder = Builder::new(GzEncoder::new(file, Compression::default()));
+    builder.follow_symlinks(false);
+
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        templates: names.iter().map(|name| name.to_string()).collect(),
+    };
+
+    append_json(&mut builder, BUNDLE_MANIFEST, &manifest)?;
+
+    for name in names {
+        let mut template_data = TemplateData::load(name).map_err(|_e| TemplateError::IoError)?;
+        let payload = template_data.payload_path();
+        let data_file = format!("{}/{}", name, store::TEMPLATE_DATA_FILE);
+
+        // Payloads saved outside the store by older versions are packed in the template's dir
+        let appended = match template_data.class() {
+            DIR_TEMPLATE => {
+                template_data.set_data_path(store::dir_payload_path(name));
+                builder.append_dir_all(store::dir_payload_path(name), &payload)
+            }
+            TXML_TEMPLATE => {
+                template_data.set_data_path(store::txml_payload_path(name));
+                builder.append_path_with_name(&payload, store::txml_payload_path(name))
+            }
+            _ => Ok(()),
+        };
+
+        appended.map_err(|_e| TemplateError::IoError)?;
+        append_json(&mut builder, &data_file, &template_data)?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|file| file.sync_all())
+        .map_err(|_e| TemplateError::IoError)
+}
+
+fn append_json<T: Serialize>(builder: &mut Builder<GzEncoder<File>>, path: &str, value: &T) -> Result<(), TemplateError> {
+    let json = serde_json::to_vec_pretty(value).map_err(|_e| TemplateError::IoError)?;
+
+    let mut header = Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(crate::templates::data::now());
+    header.set_cksum();
+
+    builder.append_data(&mut header, path, json.as_slice()).map_err(|_e| TemplateError::IoError)
+}
+
+/// How the templates of a bundle are named in the store.
+pub struct ImportOptions {
+    /// New names of the templates, by their name in the bundle.
+    pub renames: HashMap<String, String>,
+    /// Replace the templates that already exist instead of skipping them.
+    pub replace: bool,
+}
+
+/// Parses the `--rename <Name in the bundle>=<New name>` values.
+pub fn parse_renames(values: &[&str]) -> Result<HashMap<String, String>, TemplateError> {
+    values
+        .iter()
+        .map(|value| match value.split_once('=') {
+            Some((name, new_name)) if !name.is_empty() && !new_name.is_empty() => {
+                Ok((name.to_string(), new_name.to_string()))
+            }
+            _ => Err(TemplateError::InvalidRenameValue),
+        })
+        .collect()
+}
+
+/// Unpacks a bundle and adds each of its templates to the store once it is validated. Returns
+/// the names the templates were imported as, the ones that failed are reported and skipped.
+pub fn import(bundle: &Path, options: &ImportOptions) -> Result<Vec<String>, TemplateError> {
+    let _lock = store::lock()?;
+    let unpacked = store::import_dir();
+
+    let result = unpack(bundle, &unpacked).map(|manifest| {
+        let mut imported = Vec::new();
+
+        for name in &manifest.templates {
+            let target = options.renames.get(name).cloned().unwrap_or_else(|| name.clone());
+
+            match import_template(&unpacked, name, &target, options.replace) {
+                Ok(_) => imported.push(target),
+                Err(e) => println!("Error importing {}: {:?}", name, e),
+            }
+        }
+
+        imported
+    });
+
+    if let Err(e) = fs::remove_dir_all(&unpacked) {
+        println!("{} couldn't be removed: {e:?}", unpacked.display());
+    }
+
+    result
+}
+
+fn unpack(bundle: &Path, dst: &Path) -> Result<BundleManifest, TemplateError> {
+    let file = File::open(bundle).map_err(|_e| TemplateError::InvalidPath)?;
+
+    if dst.exists() {
+        fs::remove_dir_all(dst).map_err(|_e| TemplateError::IoError)?;
+    }
+
+    // Entries outside the destination, e.g. `../file`, are never unpacked
+    Archive::new(GzDecoder::new(file))
+        .unpack(dst)
+        .map_err(|e| TemplateError::InvalidBundle(e.to_string()))?;
+
+    let manifest = fs::read_to_string(dst.join(BUNDLE_MANIFEST))
+        .map_err(|_e| TemplateError::InvalidBundle(format!("{} is missing", BUNDLE_MANIFEST)))?;
+    let manifest = serde_json::from_str::<BundleManifest>(&manifest)
+        .map_err(|e| TemplateError::InvalidBundle(e.to_string()))?;
+
+    if manifest.version > BUNDLE_VERSION {
+        return Err(TemplateError::InvalidBundle(format!(
+            "version {} isn't supported, it may need a newer version",
+            manifest.version
+        )));
+    }
+
+    Ok(manifest)
+}
+
+fn import_template(unpacked: &Path, name: &str, target: &str, replace: bool) -> Result<(), TemplateError> {
+    if !is_valid_template_name(name) || !unpacked.join(name).join(store::TEMPLATE_DATA_FILE).is_file() {
+        return Err(TemplateError::InvalidBundle(format!("{} isn't in the bundle", name)));
+    }
+
+    if !is_valid_template_name(target) {
+        return Err(TemplateError::InvalidTemplateName(target.to_string()));
+    }
+
+    let previous = get_template_data(target);
+
+    if previous.is_some() && !replace {
+        return Err(TemplateError::TemplateAlreadyExists(target.to_string()));
+    }
+
+    // Validated in the staging directory, so only valid templates reach the store
+    let staging = store::new_name(target);
+
+    let staged = store::discard(&staging)
+        .and_then(|_| fs::rename(unpacked.join(name), store::template_dir(&staging)))
+        .map_err(|_e| TemplateError::IoError)
+        .and_then(|_| stage_template(&staging, name, target))
+        .and_then(|_| store::replace(&staging, target).map_err(|_e| TemplateError::IoError));
+
+    if let Err(e) = staged {
+        let _ = store::discard(&staging);
+        return Err(e);
+    }
+
+    if let Some(previous) = previous {
+        remove_replaced(&previous, target);
+    }
+
+    Ok(())
+}
+
+fn stage_template(staging: &str, name: &str, target: &str) -> Result<(), TemplateError> {
+    let mut template_data = TemplateData::load(staging).map_err(|_e| TemplateError::IoError)?;
+
+    // Only the layout written by export is trusted, any other path could point outside the store
+    let payload_path = match template_data.class() {
+        DIR_TEMPLATE => Some(store::dir_payload_path(name)),
+        TXML_TEMPLATE => Some(store::txml_payload_path(name)),
+        _ => None,
+    };
+
+    if payload_path.is_some_and(|path| path != template_data.data_path()) {
+        return Err(TemplateError::InvalidBundle(format!(
+            "{}'s payload path {} isn't in the bundle",
+            name,
+            template_data.data_path()
+        )));
+    }
+
+    template_data.rebase(name, staging);
+
+    if template_data.is_dangling() || !template_data.to_template()?.validate() {
+        return Err(TemplateError::InvalidTemplate);
+    }
+
+    template_data.rebase(staging, target);
+    template_data.save(staging).map_err(|_e| TemplateError::IoError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_renames() {
+        let renames = parse_renames(&["a=b", "c=d"]).unwrap();
+
+        assert_eq!(renames.get("a").unwrap(), "b");
+        assert_eq!(renames.get("c").unwrap(), "d");
+        assert!(parse_renames(&["a"]).is_err());
+        assert!(parse_renames(&["=b"]).is_err());
+        assert!(parse_renames(&["a="]).is_err());
+    }
+}
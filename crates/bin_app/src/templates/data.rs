@@ -1,25 +1,270 @@
 use crate::templates::dir::DirTemplate;
 use crate::templates::git::GitTemplate;
-use crate::templates::{dir, git, txml, Template};
+use crate::templates::{dir, git, store, txml, Template, TemplateError};
+use crate::BIN_NAME;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Serialize, Deserialize)]
+/// Version of the data files written by this build. Files with an older version are migrated
+/// when they are loaded.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TemplateData
 {
+    #[serde(default = "first_schema_version")]
+    schema_version: u32,
     class: String,
     data_path: String,
+    #[serde(default)]
+    created_at: u64,
+    #[serde(default)]
+    updated_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_spawned: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ignore: Vec<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    git_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subdir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    history: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
 }
 
 impl TemplateData
 {
     pub fn new(class: &str, data_path: &str) -> Self
     {
+        let now = now();
+
         Self {
+            schema_version: SCHEMA_VERSION,
             class: class.to_string(),
             data_path: data_path.to_string(),
+            created_at: now,
+            updated_at: now,
+            last_spawned: None,
+            source: None,
+            tags: Vec::new(),
+            checksum: None,
+            ignore: Vec::new(),
+            git_ref: None,
+            subdir: None,
+            history: None,
+            description: None,
+        }
+    }
+
+    pub fn class(&self) -> &str
+    {
+        self.class.as_str()
+    }
+
+    /// Url of git templates. Payloads of the other templates are relative to the templates' dir
+    /// unless they were saved by an older version.
+    pub fn data_path(&self) -> &str
+    {
+        self.data_path.as_str()
+    }
+
+    pub fn set_data_path(&mut self, data_path: String)
+    {
+        self.data_path = data_path;
+    }
+
+    /// Where the payload of a dir or txml template is.
+    pub fn payload_path(&self) -> PathBuf
+    {
+        let path = Path::new(&self.data_path);
+
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+
+        crate::templates::SAVE_TEMPLATES_DIR.join(path)
+    }
+
+    /// Moves a payload path inside the directory of the template `from` to the one of `to`.
+    pub fn rebase(&mut self, from: &str, to: &str)
+    {
+        if self.class == git::GIT_TEMPLATE {
+            return;
+        }
+
+        if let Some(rest) = self.data_path.strip_prefix(&format!("{}/", from)) {
+            self.data_path = format!("{}/{}", to, rest);
+        }
+    }
+
+    /// Url of git templates, payload path of the others.
+    pub fn location(&self) -> String
+    {
+        match self.class.as_str() {
+            git::GIT_TEMPLATE => self.data_path.clone(),
+            _ => self.payload_path().to_string_lossy().to_string(),
+        }
+    }
+
+    /// Checks if the payload of a dir or txml template no longer exists.
+    pub fn is_dangling(&self) -> bool
+    {
+        self.class != git::GIT_TEMPLATE && !self.payload_path().exists()
+    }
+
+    /// Makes an absolute payload path relative to the templates' dir. Paths inside another
+    /// templates' dir, e.g. before `MKT_HOME` was moved, are relocated if the payload is found
+    /// in the current one. Returns if the path changed.
+    pub fn relativize(&mut self) -> bool
+    {
+        let path = PathBuf::from(&self.data_path);
+
+        if self.class == git::GIT_TEMPLATE || !path.is_absolute() {
+            return false;
         }
+
+        let save_templates_dir = crate::templates::SAVE_TEMPLATES_DIR.as_path();
+
+        let relative = match path.strip_prefix(save_templates_dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => match path.file_name() {
+                Some(file_name) if !path.exists() && save_templates_dir.join(file_name).exists() => {
+                    PathBuf::from(file_name)
+                }
+                _ => return false,
+            },
+        };
+
+        self.data_path = relative.to_string_lossy().to_string();
+        true
+    }
+
+    /// Ignore patterns that were applied when the template was added.
+    pub fn ignore(&self) -> &[String]
+    {
+        &self.ignore
+    }
+
+    pub fn set_ignore(&mut self, ignore: Vec<String>)
+    {
+        self.ignore = ignore;
+    }
+
+    /// Branch, tag or commit a git template is pinned to.
+    pub fn git_ref(&self) -> Option<&str>
+    {
+        self.git_ref.as_deref()
+    }
+
+    pub fn set_git_ref(&mut self, git_ref: Option<String>)
+    {
+        self.git_ref = git_ref;
+    }
+
+    /// Subdirectory of the repository that holds a git template.
+    pub fn subdir(&self) -> Option<&str>
+    {
+        self.subdir.as_deref()
+    }
+
+    pub fn set_subdir(&mut self, subdir: Option<String>)
+    {
+        self.subdir = subdir;
+    }
+
+    /// What to do with the history of a spawned git template: keep, strip or fresh.
+    pub fn history(&self) -> Option<&str>
+    {
+        self.history.as_deref()
+    }
+
+    pub fn set_history(&mut self, history: Option<String>)
+    {
+        self.history = history;
+    }
+
+    /// Description cached when the template was added or updated.
+    pub fn description(&self) -> Option<&str>
+    {
+        self.description.as_deref()
+    }
+
+    pub fn set_description(&mut self, description: Option<String>)
+    {
+        self.description = description;
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn created_at(&self) -> u64
+    {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> u64
+    {
+        self.updated_at
+    }
+
+    pub fn touch(&mut self)
+    {
+        self.updated_at = now();
+    }
+
+    pub fn last_spawned(&self) -> Option<u64>
+    {
+        self.last_spawned
+    }
+
+    pub fn mark_spawned(&mut self)
+    {
+        self.last_spawned = Some(now());
+    }
+
+    /// The path or url the template was added from.
+    pub fn source(&self) -> Option<&str>
+    {
+        self.source.as_deref()
+    }
+
+    pub fn set_source(&mut self, source: Option<String>)
+    {
+        self.source = source;
+    }
+
+    pub fn tags(&self) -> &[String]
+    {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>)
+    {
+        self.tags = tags;
+    }
+
+    /// SHA-256 of the stored payload. Git templates have no payload.
+    pub fn checksum(&self) -> Option<&str>
+    {
+        self.checksum.as_deref()
+    }
+
+    pub fn update_checksum(&mut self)
+    {
+        self.checksum = match self.class.as_str() {
+            git::GIT_TEMPLATE => None,
+            _ => payload_checksum(&self.payload_path()).ok(),
+        };
     }
 
     pub fn from_json(json: &str) -> serde_json::Result<Self>
@@ -27,34 +272,224 @@ impl TemplateData
         serde_json::from_str(json)
     }
 
+    /// Saves the data of the template. Data written with a newer schema is never saved, the
+    /// fields this version doesn't know would be lost.
     pub fn save(&self, name: &str) -> Result<(), io::Error>
     {
-        let json_data =
-            serde_json::to_string_pretty(&self).expect("Should serialize the template.");
-        let dst = crate::templates::SAVE_TEMPLATES_DIR.as_path().join(name);
-        let dst = dst.as_path();
+        if self.schema_version > SCHEMA_VERSION {
+            return Err(io::Error::other(format!(
+                "The data of {} was written by a newer version of {}, update it to change the template",
+                name, BIN_NAME
+            )));
+        }
+
+        let json_data = serde_json::to_string_pretty(&self).map_err(io::Error::other)?;
 
-        std::fs::write(dst, json_data)
+        fs::create_dir_all(store::template_dir(name))?;
+        store::write_atomic(&store::data_file(name), json_data.as_bytes())
     }
 
+    /// Loads the data of a template, migrating and saving it first if it was written with an
+    /// older schema. Data written with a newer schema is loaded as is and can't be saved.
     pub fn load(name: &str) -> Result<Self, io::Error>
     {
-        let src = crate::templates::SAVE_TEMPLATES_DIR.as_path().join(name);
-        let src = src.as_path();
-        let data = std::fs::read_to_string(src)?;
+        let template_data = Self::read(name)?;
+
+        if template_data.schema_version >= SCHEMA_VERSION {
+            return Ok(template_data);
+        }
+
+        // Read again under the lock, another process may have migrated it meanwhile
+        let lock = store::lock();
+        let mut template_data = Self::read(name)?;
+
+        if template_data.schema_version < SCHEMA_VERSION {
+            let modified = fs::metadata(store::data_file(name))
+                .and_then(|metadata| metadata.modified())
+                .map(seconds_since_epoch)
+                .unwrap_or_else(|_| now());
+
+            template_data.migrate(modified);
 
-        let template_data: TemplateData = Self::from_json(data.as_str())?;
+            // Without the lock the migration is only kept in memory
+            let saved = match lock {
+                Ok(_) => template_data.save(name),
+                Err(e) => Err(io::Error::other(format!("{:?}", e))),
+            };
+
+            if let Err(e) = saved {
+                eprintln!("The data of {} couldn't be migrated: {e:?}", name);
+            }
+        }
 
         Ok(template_data)
     }
-    
-    pub fn to_template(&self) -> Box<dyn Template>
+
+    fn read(name: &str) -> Result<Self, io::Error>
+    {
+        let data = fs::read_to_string(store::data_file(name))?;
+
+        Ok(Self::from_json(data.as_str())?)
+    }
+
+    /// Fills the fields added since the schema the data was written with.
+    fn migrate(&mut self, modified: u64)
+    {
+        if self.schema_version < 2 {
+            self.created_at = modified;
+            self.updated_at = modified;
+
+            if self.class == git::GIT_TEMPLATE {
+                self.source = Some(self.data_path.clone());
+            }
+
+            self.update_checksum();
+        }
+
+        self.schema_version = SCHEMA_VERSION;
+    }
+
+    pub fn to_template(&self) -> Result<Box<dyn Template>, TemplateError>
     {
         match self.class.as_str() {
-            dir::DIR_TEMPLATE => Box::new(DirTemplate::new(PathBuf::from(self.data_path.as_str()))),
-            git::GIT_TEMPLATE => Box::new(GitTemplate::new(self.data_path.as_str())),
-            txml::TXML_TEMPLATE => Box::new(txml::TxmlTemplate::new(PathBuf::from(self.data_path.as_str()))),
-            _ => panic!("Invalid template class."),
+            dir::DIR_TEMPLATE => Ok(Box::new(DirTemplate::new(self.payload_path()))),
+            git::GIT_TEMPLATE => Ok(Box::new(GitTemplate::from_data(self))),
+            txml::TXML_TEMPLATE => Ok(Box::new(txml::TxmlTemplate::new(self.payload_path()))),
+            class => Err(TemplateError::UnknownTemplateClass(class.to_string())),
+        }
+    }
+}
+
+fn first_schema_version() -> u32
+{
+    1
+}
+
+pub fn now() -> u64
+{
+    seconds_since_epoch(SystemTime::now())
+}
+
+fn seconds_since_epoch(time: SystemTime) -> u64
+{
+    time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+/// Formats seconds since the Unix epoch as an UTC date, e.g. `2024-09-23 18:05:00 UTC`.
+pub fn format_timestamp(seconds: u64) -> String
+{
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// SHA-256 of a file or of every entry of a directory, in name order, with its relative path.
+fn payload_checksum(path: &Path) -> io::Result<String>
+{
+    let mut hasher = Sha256::new();
+
+    if path.is_dir() {
+        hash_dir(&mut hasher, path, Path::new(""))?;
+    } else {
+        hasher.update(fs::read(path)?);
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn hash_dir(hasher: &mut Sha256, dir: &Path, relative: &Path) -> io::Result<()>
+{
+    let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, io::Error>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+
+        if file_type.is_symlink() {
+            hasher.update(fs::read_link(&path)?.to_string_lossy().as_bytes());
+        } else if file_type.is_dir() {
+            hash_dir(hasher, &path, &relative)?;
+        } else {
+            hasher.update(fs::read(&path)?);
         }
+
+        hasher.update([0]);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_migrate_from_first_schema()
+    {
+        let mut template_data =
+            TemplateData::from_json(r#"{"class": "git", "data_path": "https://host/repo.git"}"#).unwrap();
+
+        assert_eq!(template_data.schema_version, 1);
+
+        template_data.migrate(1727114700);
+
+        assert_eq!(template_data.schema_version, SCHEMA_VERSION);
+        assert_eq!(template_data.created_at(), 1727114700);
+        assert_eq!(template_data.source(), Some("https://host/repo.git"));
+        assert!(template_data.checksum().is_none());
+    }
+
+    #[test]
+    fn test_newer_schema_is_not_saved()
+    {
+        let template_data = TemplateData::from_json(&format!(
+            r#"{{"schema_version": {}, "class": "git", "data_path": "https://host/repo.git", "mirror": "x"}}"#,
+            SCHEMA_VERSION + 1
+        ))
+        .unwrap();
+
+        assert!(template_data.save("newer").is_err());
+    }
+
+    #[test]
+    fn test_unknown_class()
+    {
+        let template_data = TemplateData::new("svn", "https://host/repo");
+
+        assert!(matches!(template_data.to_template(), Err(TemplateError::UnknownTemplateClass(_))));
+    }
+
+    #[test]
+    fn test_format_timestamp()
+    {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(1727114700), "2024-09-23 18:05:00 UTC");
+        assert_eq!(format_timestamp(951782400), "2000-02-29 00:00:00 UTC");
     }
 }
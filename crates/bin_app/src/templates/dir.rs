@@ -1,12 +1,19 @@
+use crate::commands::flag_values;
 use crate::templates::data::TemplateData;
-use crate::templates::{is_valid_name, Template, TemplateError, SAVE_TEMPLATES_DIR};
+use crate::templates::ignore::{IgnoreRules, MKT_IGNORE_FILE};
+use crate::templates::manifest::{Manifest, MANIFEST_FILE};
+use crate::templates::{is_valid_name, store, variable_values, Template, TemplateError};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 use txml_processor::txml_structure::TxmlStructure;
+use txml_processor::variables::{replace_literals, substitute_all, templatize_pairs};
 use txml_processor::TxmlElement;
 use crate::templates::txml::TXML_TEMPLATE;
 
+/// Transformation applied to the names and text contents while copying a directory.
+type Transform<'a> = &'a dyn Fn(&str) -> String;
+
 pub const DIR_TEMPLATE: &str = "dir";
 
 pub struct DirTemplate {
@@ -18,44 +25,109 @@ impl DirTemplate {
         Self { dir }
     }
 
-    pub fn save_as_dir(&self, name: &str) -> Result<(), TemplateError> {
+    /// Rules applied when the template is added: the `.mktignore` of the source dir,
+    /// its `.gitignore` if `--gitignore` is set and every `--exclude <glob>`.
+    pub fn ignore_rules(&self, flags: &HashMap<String, Vec<String>>) -> IgnoreRules {
+        let mut excluded = vec![MKT_IGNORE_FILE];
+        excluded.extend(flag_values(flags, "--exclude"));
+
+        IgnoreRules::for_dir(&self.dir, flags.contains_key("--gitignore"), &excluded)
+    }
+
+    /// Values given with `--templatize <literal>=<variable>` that should become variables.
+    pub fn templatize_values(flags: &HashMap<String, Vec<String>>) -> Result<Vec<(&str, &str)>, TemplateError> {
+        flag_values(flags, "--templatize")
+            .into_iter()
+            .map(|value| match value.rsplit_once('=') {
+                Some((literal, variable)) if !literal.is_empty() && is_valid_variable_name(variable) => {
+                    Ok((literal, variable))
+                }
+                _ => Err(TemplateError::InvalidTemplatizeValue),
+            })
+            .collect()
+    }
+
+    pub fn save_as_dir(
+        &self,
+        name: &str,
+        ignore_rules: &IgnoreRules,
+        templatize: &[(&str, &str)],
+        detect_cases: bool,
+    ) -> Result<(), TemplateError> {
         let src = self.dir.as_path();
-        let dst = SAVE_TEMPLATES_DIR.as_path().join(name.to_string() + ".dir");
+        let dst = store::payload_dir(name);
         let dst = dst.as_path();
 
-        let result = copy_dir_all(src, dst).map_err(|_e| TemplateError::IoError);
+        let pairs = templatize_pairs(templatize, detect_cases);
+        let transform = |text: &str| replace_literals(text, &pairs);
+        let transform: Option<Transform> = if pairs.is_empty() { None } else { Some(&transform) };
 
-        if result.is_err() {
-            return result;
+        copy_dir_all(src, dst, &|path| !is_ignored(src, path, ignore_rules), transform)
+            .map_err(|_e| TemplateError::IoError)?;
+
+        if !templatize.is_empty() {
+            // The project's own mkt.toml would be overwritten by the manifest
+            if Manifest::is_foreign(dst) {
+                return Err(TemplateError::InvalidManifest);
+            }
+
+            let mut manifest = Manifest::load(dst)?.unwrap_or_default();
+            templatize.iter().for_each(|(_, variable)| manifest.declare_variable(variable));
+            manifest.save(dst)?;
         }
 
-        TemplateData::new(DIR_TEMPLATE, dst.to_str().expect("Should be an String"))
+        let mut template_data = TemplateData::new(DIR_TEMPLATE, &store::dir_payload_path(name));
+        template_data.set_ignore(ignore_rules.patterns());
+        template_data
             .save(name)
-            .map_err(|_e| {
-                fs::remove_dir(dst).expect("Should remove the directory.");
-                TemplateError::IoError
-            })
+            .map_err(|_e| TemplateError::IoError)
     }
 
-    pub fn save_as_fxml(&self, name: &str) -> Result<(), TemplateError> {
-        let txml_content = TxmlStructure::from_path(&self.dir)
-            .map_err(|_e| TemplateError::ErrorConvertingDir2Txml)?.into_txml_element();
-        
-        let txml_file = SAVE_TEMPLATES_DIR.as_path().join(name.to_string() + ".txml");
+    pub fn save_as_fxml(
+        &self,
+        name: &str,
+        ignore_rules: &IgnoreRules,
+        templatize: &[(&str, &str)],
+        detect_cases: bool,
+    ) -> Result<(), TemplateError> {
+        let src = self.dir.as_path();
+        let manifest = Manifest::load(src)?;
+        let manifest_file = manifest.as_ref().map(|_| src.join(MANIFEST_FILE));
+        let mut txml_structure = TxmlStructure::from_path_filtered(src, &|path| {
+            Some(path) != manifest_file.as_deref() && !is_ignored(src, path, ignore_rules)
+        })
+        .map_err(|_e| TemplateError::ErrorConvertingDir2Txml)?;
+
+        // The description and variables of the manifest go to the TXML Metadata and Variables
+        if let Some(manifest) = manifest {
+            if let Some(description) = manifest.description() {
+                txml_structure.set_description(description);
+            }
+
+            manifest.variables().into_iter().for_each(|variable| txml_structure.add_variable(variable));
+        }
+
+        if !templatize.is_empty() {
+            txml_structure.templatize(templatize, detect_cases);
+        }
+
+        let txml_content = txml_structure.into_txml_element();
         
+        let txml_file = store::payload_dir(name).join(store::TXML_PAYLOAD_FILE);
+
+        fs::create_dir_all(store::payload_dir(name)).map_err(|_e| TemplateError::IoError)?;
         fs::write(&txml_file, txml_content).map_err(|_e| TemplateError::IoError)?;
-        
-        TemplateData::new(TXML_TEMPLATE, txml_file.to_str().expect("Should be an String"))
+
+        let mut template_data = TemplateData::new(TXML_TEMPLATE, &store::txml_payload_path(name));
+        template_data.set_ignore(ignore_rules.patterns());
+        template_data
             .save(name)
-            .map_err(|_e| {
-                fs::remove_file(txml_file).expect("Should remove the file.");
-                TemplateError::IoError
-            })
+            .map_err(|_e| TemplateError::IoError)
     }
 }
 
 impl Template for DirTemplate {
-    fn generate(&self, name: &str, _flags: HashMap<String, String>) -> Result<(), TemplateError> {
+    fn generate(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
         if !is_valid_name(name) {
             return Err(TemplateError::InvalidTemplate);
         }
@@ -63,23 +135,50 @@ impl Template for DirTemplate {
         let src = self.dir.as_path();
         let dst = PathBuf::from(name);
 
-        copy_dir_all(src, &dst).map_err(|_e| TemplateError::IoError)
+        let values = variable_values(&flags)?;
+        let manifest = Manifest::load(src)?;
+        let variables = manifest
+            .as_ref()
+            .map(|manifest| manifest.resolve_variables(&values))
+            .unwrap_or_default();
+        let transform = |text: &str| substitute_all(text, &variables);
+        let transform: Option<Transform> = if variables.is_empty() { None } else { Some(&transform) };
+        let manifest_file = manifest.as_ref().map(|_| src.join(MANIFEST_FILE));
+
+        copy_dir_all(src, &dst, &|path| Some(path) != manifest_file.as_deref(), transform)
+            .map_err(|_e| TemplateError::IoError)
     }
 
-    fn save(&self, name: &str, flags: HashMap<String, String>) -> Result<(), TemplateError> {
+    fn save(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        let ignore_rules = self.ignore_rules(&flags);
+        let templatize = Self::templatize_values(&flags)?;
+        let detect_cases = flags.contains_key("--templatize-cases");
+
+        if Manifest::is_foreign(&self.dir) {
+            println!("The {} of {} isn't a manifest, it is kept as one more file.", MANIFEST_FILE, self.dir.display());
+        }
+
         if flags.contains_key("-as-dir") {
-            self.save_as_dir(name)
+            self.save_as_dir(name, &ignore_rules, &templatize, detect_cases)
         } else {
-            if let Err(_) = self.save_as_fxml(name) {
-                self.save_as_dir(name)
-            } else {
-                Ok(())
+            // Only a directory that can't be read as TXML is kept as a directory, other errors,
+            // e.g. an invalid manifest, are the user's to fix
+            match self.save_as_fxml(name, &ignore_rules, &templatize, detect_cases) {
+                Err(TemplateError::ErrorConvertingDir2Txml) => self.save_as_dir(name, &ignore_rules, &templatize, detect_cases),
+                result => result,
             }
         }
     }
 
     fn remove(&self) {
-        fs::remove_dir_all(&self.dir).expect("Should remove the directory.");
+        // The payload lives in the template's directory, it is only left by older versions
+        if !self.dir.exists() {
+            return;
+        }
+
+        if let Err(e) = store::remove_payload(&self.dir) {
+            println!("The Dir Template couldn't be deleted: {e:?}")
+        }
     }
 
     fn validate(&self) -> bool {
@@ -91,7 +190,26 @@ impl Template for DirTemplate {
     }
 }
 
-fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
+fn is_valid_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+fn is_ignored(root: &Path, path: &Path, ignore_rules: &IgnoreRules) -> bool {
+    let relative_path = path.strip_prefix(root).unwrap_or(path);
+    let is_dir = path.symlink_metadata().map(|m| m.is_dir()).unwrap_or(false);
+
+    ignore_rules.is_ignored(relative_path, is_dir)
+}
+
+/// Copies `src` into `dst` skipping every entry, at any depth, for which `filter` returns false.
+/// The `transform` is applied to every name and to the content of the text files; binary files
+/// are always copied untouched.
+pub fn copy_dir_all(
+    src: &Path,
+    dst: &Path,
+    filter: &dyn Fn(&Path) -> bool,
+    transform: Option<Transform>,
+) -> io::Result<()> {
     if !dst.exists() {
         fs::create_dir_all(dst)?;
     }
@@ -99,14 +217,70 @@ fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
     for entry in fs::read_dir(src)? {
         let entry = entry?;
         let path = entry.path();
-        let dest_path = dst.join(entry.file_name());
+        let file_type = entry.file_type()?;
+
+        if !filter(&path) {
+            continue;
+        }
+
+        let dest_path = match (transform, entry.file_name().to_str()) {
+            (Some(transform), Some(file_name)) => dst.join(transform(file_name)),
+            _ => dst.join(entry.file_name()),
+        };
 
-        if path.is_dir() {
-            copy_dir_all(&path, &dest_path)?;
+        if file_type.is_symlink() {
+            copy_symlink(&path, &dest_path, transform)?;
+        } else if file_type.is_dir() {
+            copy_dir_all(&path, &dest_path, filter, transform)?;
         } else {
-            fs::copy(&path, &dest_path)?;
+            copy_file(&path, &dest_path, transform)?;
         }
     }
 
-    Ok(())
+    // Applied last so a read-only directory doesn't prevent copying its content
+    fs::set_permissions(dst, fs::metadata(src)?.permissions())
+}
+
+fn copy_file(src: &Path, dst: &Path, transform: Option<Transform>) -> io::Result<()> {
+    let transform = match transform {
+        Some(transform) => transform,
+        // fs::copy already preserves the permission bits
+        None => return fs::copy(src, dst).map(|_| ()),
+    };
+
+    let content = fs::read(src)?;
+
+    match String::from_utf8(content) {
+        Ok(text) if !text.contains('\0') => fs::write(dst, transform(&text))?,
+        Ok(text) => fs::write(dst, text)?,
+        Err(e) => fs::write(dst, e.into_bytes())?,
+    }
+
+    fs::set_permissions(dst, fs::metadata(src)?.permissions())
+}
+
+/// Recreates the symlink `src` at `dst`, with `transform` applied to its target.
+#[cfg(unix)]
+pub fn copy_symlink(src: &Path, dst: &Path, transform: Option<Transform>) -> io::Result<()> {
+    if dst.symlink_metadata().is_ok() {
+        fs::remove_file(dst)?;
+    }
+
+    let target = fs::read_link(src)?;
+    let target = match (transform, target.to_str()) {
+        (Some(transform), Some(target)) => PathBuf::from(transform(target)),
+        _ => target,
+    };
+
+    std::os::unix::fs::symlink(target, dst)
+}
+
+#[cfg(not(unix))]
+pub fn copy_symlink(src: &Path, dst: &Path, transform: Option<Transform>) -> io::Result<()> {
+    // Symlinks can't be recreated without privileges, the target is copied instead
+    if src.is_dir() {
+        copy_dir_all(src, dst, &|_| true, transform)
+    } else {
+        copy_file(src, dst, transform)
+    }
 }
@@ -0,0 +1,43 @@
+use crate::templates::TemplateError;
+use std::io::{self, Write};
+use std::path::Path;
+use std::{env, process};
+
+/// Command of the user's editor, `$VISUAL` first and `$EDITOR` otherwise, split in the program
+/// and its arguments, e.g. `code --wait`.
+fn editor_command() -> Option<Vec<String>> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .map(|command| command.split_whitespace().map(String::from).collect::<Vec<String>>())
+        .find(|command| !command.is_empty())
+}
+
+/// Opens the path in the user's editor and waits until it is closed.
+pub fn open(path: &Path) -> Result<(), TemplateError> {
+    let command = editor_command().ok_or(TemplateError::EditorNotSet)?;
+    let status = process::Command::new(&command[0])
+        .args(&command[1..])
+        .arg(path)
+        .status()
+        .map_err(|_e| TemplateError::EditorFailed(command[0].clone()))?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(TemplateError::EditorFailed(command[0].clone())),
+    }
+}
+
+/// Asks whether an invalid template should be edited again. Any other answer, or none at all,
+/// reverts it.
+pub fn ask_reopen() -> bool {
+    print!("[r]e-open the editor or re[v]ert the changes? ");
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+
+    match io::stdin().read_line(&mut answer) {
+        Ok(_) => answer.trim().eq_ignore_ascii_case("r"),
+        Err(_) => false,
+    }
+}
@@ -1,78 +1,608 @@
+use crate::commands::flag_value;
 use crate::templates::data::TemplateData;
-use crate::templates::{is_valid_name, Template, TemplateError, SAVE_TEMPLATES_DIR};
+use crate::templates::manifest::{Manifest, MANIFEST_FILE};
+use crate::templates::source::is_local_path;
+use crate::templates::txml::TxmlTemplate;
+use crate::templates::{get_available_templates, is_valid_name, PendingStep, Template, TemplateError};
+use crate::CONFIG_DIR;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::{fs, process};
+use txml_processor::txml_structure::TxmlStructure;
 
 pub const GIT_TEMPLATE: &str = "git";
 
+/// Bare mirrors of the git templates, one per url. Spawns clone from them.
+pub static GIT_CACHE_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("cache").join("git"));
+
 pub struct GitTemplate {
     url: String,
+    git_ref: Option<String>,
+    subdir: Option<String>,
+    history: History,
+    description: Option<String>,
+}
+
+/// What happens to the repository of a spawned git template.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum History {
+    /// The clone is left as is, with its history and its `origin` remote.
+    #[default]
+    Keep,
+    /// The `.git` directory is removed.
+    Strip,
+    /// The `.git` directory is replaced by a new repository with an initial commit.
+    Fresh,
+}
+
+impl History {
+    pub fn name(&self) -> &'static str {
+        match self {
+            History::Keep => "keep",
+            History::Strip => "strip",
+            History::Fresh => "fresh",
+        }
+    }
+}
+
+impl FromStr for History {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(History::Keep),
+            "strip" => Ok(History::Strip),
+            "fresh" => Ok(History::Fresh),
+            _ => Err(TemplateError::InvalidHistoryMode),
+        }
+    }
 }
 
 impl GitTemplate {
     pub fn new(url: &str) -> Self {
         Self {
             url: url.to_string(),
+            git_ref: None,
+            subdir: None,
+            history: History::default(),
+            description: None,
         }
     }
-}
 
-impl Template for GitTemplate {
-    fn generate(&self, name: &str, _flags: HashMap<String, String>) -> Result<(), TemplateError> {
+    pub fn from_data(template_data: &TemplateData) -> Self {
+        Self {
+            url: template_data.data_path().to_string(),
+            git_ref: template_data.git_ref().map(String::from),
+            subdir: template_data.subdir().map(String::from),
+            history: template_data
+                .history()
+                .and_then(|history| history.parse().ok())
+                .unwrap_or_default(),
+            description: template_data.description().map(String::from),
+        }
+    }
+
+    fn mirror_path(&self) -> PathBuf {
+        GIT_CACHE_DIR.join(format!("{:016x}", fnv1a_hash(self.url.as_bytes())))
+    }
+
+    /// Returns the mirror of the repository, creating it when it doesn't exist yet and fetching
+    /// its new commits otherwise. Both need network access, so with `offline` set an existing
+    /// mirror is used as is and a missing one is an error.
+    fn ensure_mirror(&self, offline: bool) -> Result<PathBuf, TemplateError> {
+        let mirror = self.mirror_path();
+
+        if mirror.exists() {
+            // The cached commits are still good enough to spawn from
+            if !offline {
+                if let Err(e) = run_git(&["fetch", "--prune", "--quiet"], Some(&mirror)) {
+                    eprintln!("The mirror of {} couldn't be fetched, its cached commits are used: {:?}", self.url, e);
+                }
+            }
+
+            return Ok(mirror);
+        }
+
+        if offline {
+            return Err(TemplateError::GitMirrorMissing);
+        }
+
+        fs::create_dir_all(GIT_CACHE_DIR.as_path()).map_err(|_e| TemplateError::IoError)?;
+        run_git(&["clone", "--mirror", "--quiet", &self.url, &mirror.to_string_lossy()], None)?;
+
+        Ok(mirror)
+    }
+
+    /// Fetches the new commits of the repository into its mirror.
+    pub fn update_mirror(&self) -> Result<(), TemplateError> {
+        let mirror = self.mirror_path();
+
+        if !mirror.exists() {
+            return self.ensure_mirror(false).map(|_| ());
+        }
+
+        run_git(&["fetch", "--prune", "--quiet"], Some(&mirror))
+    }
+
+    /// Reads the description from the mirror, at the pinned ref and inside the subdirectory if
+    /// any: the description of a `mkt.toml` or of the Metadata of a TXML file, then the first
+    /// paragraph of the README and then the description of the repository.
+    pub fn read_description(&self) -> Option<String> {
+        let mirror = self.mirror_path();
+
+        if !mirror.exists() {
+            return None;
+        }
+
+        let rev = self.git_ref.as_deref().unwrap_or("HEAD");
+        let base = self.subdir.as_ref().map(|subdir| format!("{}/", subdir)).unwrap_or_default();
+        let read = |file: &str| git_output(&["show", &format!("{}:{}{}", rev, base, file)], Some(&mirror)).ok();
+
+        let ls_tree = match base.is_empty() {
+            true => vec!["ls-tree", "--name-only", rev],
+            false => vec!["ls-tree", "--name-only", rev, &base],
+        };
+
+        let mut files = git_output(&ls_tree, Some(&mirror))
+            .ok()?
+            .lines()
+            .map(|path| path.strip_prefix(base.as_str()).unwrap_or(path).to_string())
+            .collect::<Vec<String>>();
+        files.sort();
+
+        let from_manifest = || {
+            read(MANIFEST_FILE)
+                .and_then(|content| Manifest::from_toml(&content).ok())
+                .and_then(|manifest| manifest.description().map(String::from))
+        };
+
+        let from_txml = || {
+            files
+                .iter()
+                .filter(|file| file.ends_with(".txml") || file.ends_with(".xml"))
+                .filter_map(|file| read(file))
+                .find_map(|content| TxmlStructure::obtain_metadata(&content).ok())
+                .map(|metadata| metadata.description)
+        };
+
+        let from_readme = || {
+            files
+                .iter()
+                .find(|file| file.to_lowercase().starts_with("readme"))
+                .and_then(|file| read(file))
+                .and_then(|content| first_paragraph(&content))
+        };
+
+        // Mirrors don't get the description of remote repositories, only local ones can be read
+        let from_repository = || {
+            let mut files = vec![mirror.join("description")];
+
+            if is_local_path(&self.url) {
+                let local = Path::new(&self.url);
+                files.splice(0..0, [local.join(".git").join("description"), local.join("description")]);
+            }
+
+            files
+                .iter()
+                .filter_map(|file| fs::read_to_string(file).ok())
+                .find(|description| !description.starts_with("Unnamed repository"))
+        };
+
+        [from_manifest(), from_txml(), from_readme(), from_repository()]
+            .into_iter()
+            .flatten()
+            .map(|description| description.trim().to_string())
+            .find(|description| !description.is_empty())
+    }
+
+    /// Clones the repository, or its subdirectory, as the output. A staged spawn leaves the
+    /// history of the clone, and the commands of a TXML subdirectory, as pending steps.
+    fn spawn(&self, name: &str, flags: HashMap<String, Vec<String>>, staged: bool) -> Result<Vec<PendingStep>, TemplateError> {
         if !is_valid_name(name) {
             return Err(TemplateError::InvalidTemplate);
         }
 
-        let mut process = match process::Command::new("git")
-            .args(&["clone", &self.url, name])
-            .spawn()
-        {
-            Ok(process) => process,
-            Err(_) => return Err(TemplateError::ErrorExecutingGit),
+        let git_ref = match flag_value(&flags, "--ref") {
+            Some(git_ref) if !git_ref.is_empty() => Some(git_ref.to_string()),
+            _ => self.git_ref.clone(),
+        };
+
+        let history = match flag_value(&flags, "--history") {
+            Some(history) => history.parse()?,
+            None => self.history,
+        };
+
+        let offline = flags.contains_key("--offline");
+
+        let mut steps = match &self.subdir {
+            Some(subdir) => self.generate_subdir(git_ref.as_deref(), offline, subdir, name, flags, staged)?,
+            None => self.clone_repository(git_ref.as_deref(), offline, name).map(|_| Vec::new())?,
+        };
+
+        match staged {
+            true => steps.push(PendingStep::History(PathBuf::from(name), history)),
+            false => apply_history(Path::new(name), history)?,
+        }
+
+        Ok(steps)
+    }
+
+    /// Clones the repository next to the output and only keeps the subdirectory. If it holds
+    /// a TXML file, it is spawned as a TXML template instead of being copied.
+    fn generate_subdir(
+        &self,
+        git_ref: Option<&str>,
+        offline: bool,
+        subdir: &str,
+        name: &str,
+        flags: HashMap<String, Vec<String>>,
+        staged: bool,
+    ) -> Result<Vec<PendingStep>, TemplateError> {
+        let clone_dir = format!(".{}.mkt-clone", name);
+        let clone_path = PathBuf::from(&clone_dir);
+
+        if clone_path.exists() {
+            fs::remove_dir_all(&clone_path).map_err(|_e| TemplateError::IoError)?;
+        }
+
+        let result = self.clone_repository(git_ref, offline, &clone_dir).and_then(|_| {
+            let subtree = clone_path.join(subdir);
+
+            if !subtree.is_dir() {
+                return Err(TemplateError::GitSubdirNotFound);
+            }
+
+            match find_txml_file(&subtree) {
+                Some(txml_file) if staged => TxmlTemplate::new(txml_file).generate_staged(name, flags),
+                Some(txml_file) => TxmlTemplate::new(txml_file).generate(name, flags).map(|_| Vec::new()),
+                None => fs::rename(&subtree, name).map(|_| Vec::new()).map_err(|_e| TemplateError::IoError),
+            }
+        });
+
+        if let Err(e) = fs::remove_dir_all(&clone_path) {
+            eprintln!("The temporary clone {} couldn't be removed: {e:?}", clone_dir);
+        }
+
+        result
+    }
+
+    /// Checks that the url is an existing local path or a remote git answers to.
+    fn check_source(&self) -> Result<(), TemplateError> {
+        if is_local_path(&self.url) && !Path::new(&self.url).exists() {
+            return Err(TemplateError::GitSourceUnreachable);
+        }
+
+        match run_git(&["ls-remote", "--quiet", &self.url, "HEAD"], None) {
+            Err(TemplateError::GitCommandFailed(_)) => Err(TemplateError::GitSourceUnreachable),
+            result => result,
+        }
+    }
+
+    /// Clones the mirror of the repository checking out the ref, if any, and points the
+    /// `origin` remote of the clone back to the repository. Branches and tags are cloned with
+    /// depth 1, commits need the whole history to be checked out.
+    fn clone_repository(&self, git_ref: Option<&str>, offline: bool, name: &str) -> Result<(), TemplateError> {
+        // Checking out a tag or a commit leaves a detached HEAD, nothing the user should be warned about
+        let quiet_detached = ["-c", "advice.detachedHead=false"];
+        let mirror = self.ensure_mirror(offline)?;
+        let mirror = mirror.to_string_lossy();
+        // Git ignores the depth of clones from a plain local path
+        let mirror_url = file_url(Path::new(mirror.as_ref()));
+
+        match git_ref {
+            None => run_git(&["clone", "--quiet", &mirror, name], None)?,
+            Some(git_ref) if is_commit_hash(git_ref) => {
+                run_git(&["clone", "--quiet", &mirror, name], None)?;
+                run_git(&[&quiet_detached[..], &["checkout", "--quiet", git_ref]].concat(), Some(Path::new(name)))?
+            }
+            Some(git_ref) => run_git(
+                &[&quiet_detached[..], &["clone", "--quiet", "--depth", "1", "--branch", git_ref, &mirror_url, name]].concat(),
+                None,
+            )?,
+        }
+
+        // Relative local paths would be resolved from the clone
+        let origin = match is_local_path(&self.url) {
+            true => fs::canonicalize(&self.url)
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|_| self.url.clone()),
+            false => self.url.clone(),
         };
 
-        process.wait().expect("Failed to wait for the process.");
-        Ok(())
+        run_git(&["remote", "set-url", "origin", &origin], Some(Path::new(name)))
+    }
+}
+
+impl Template for GitTemplate {
+    fn generate(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        self.spawn(name, flags, false).map(|_| ())
     }
 
-    fn save(&self, name: &str, _flags: HashMap<String, String>) -> Result<(), TemplateError> {
-        let template_data = TemplateData::new(GIT_TEMPLATE, &self.url);
-        let json_data =
-            serde_json::to_string_pretty(&template_data).expect("Should serialize the template.");
-        let dst = SAVE_TEMPLATES_DIR.as_path().join(name);
-        let dst = dst.as_path();
+    fn generate_staged(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<Vec<PendingStep>, TemplateError> {
+        self.spawn(name, flags, true)
+    }
+
+    fn save(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        self.check_source()?;
+
+        let mut template_data = TemplateData::new(GIT_TEMPLATE, &self.url);
 
-        std::fs::write(dst, json_data).expect("Should write the template.");
-        Ok(())
+        match flag_value(&flags, "--ref") {
+            Some(git_ref) if !git_ref.is_empty() => template_data.set_git_ref(Some(git_ref.to_string())),
+            _ => template_data.set_git_ref(self.git_ref.clone()),
+        }
+
+        match flag_value(&flags, "--subdir") {
+            Some(subdir) if !subdir.is_empty() => {
+                if !is_valid_subdir(subdir) {
+                    return Err(TemplateError::InvalidPath);
+                }
+
+                template_data.set_subdir(Some(subdir.trim_matches('/').to_string()))
+            }
+            _ => template_data.set_subdir(self.subdir.clone()),
+        }
+
+        match flag_value(&flags, "--history") {
+            Some(history) => {
+                let history = history.parse::<History>()?;
+                template_data.set_history(Some(history.name().to_string()))
+            }
+            None => template_data.set_history(
+                Some(self.history).filter(|h| *h != History::Keep).map(|h| h.name().to_string()),
+            ),
+        }
+
+        self.ensure_mirror(false)?;
+
+        // Read now so listing the templates doesn't need git
+        let description = GitTemplate::from_data(&template_data).read_description();
+        template_data.set_description(description);
+
+        template_data.save(name).map_err(|_e| TemplateError::IoError)
     }
 
     fn remove(&self) {
-        // Nothing to do here
+        // Called once the template is gone, other templates may still share the mirror
+        let users = get_available_templates()
+            .iter()
+            .filter(|(data, _)| data.class() == GIT_TEMPLATE && data.data_path() == self.url)
+            .count();
+
+        let mirror = self.mirror_path();
+
+        if users == 0 && mirror.exists() {
+            if let Err(e) = fs::remove_dir_all(&mirror) {
+                eprintln!("The cached mirror {} couldn't be removed: {e:?}", mirror.display());
+            }
+        }
     }
 
     fn validate(&self) -> bool {
-        true
+        self.check_source().is_ok()
     }
 
     fn get_description(&self) -> String {
-        let file_path = PathBuf::from(&self.url);
+        self.description
+            .clone()
+            .or_else(|| self.read_description())
+            .unwrap_or_else(|| "Could not obtain the description.".to_string())
+    }
+}
 
-        if file_path.is_dir() {
-            return "A git template from a reachable directory.".to_string();
-        }
+/// Version of the git on PATH, if there is one.
+pub fn git_version() -> Option<String> {
+    git_output(&["--version"], None).ok().map(|version| version.trim().to_string())
+}
 
-        get_git_template_description(&self.url)
-            .unwrap_or_else(|| "Could not obtain the description.".to_string())
+fn run_git(args: &[&str], dir: Option<&Path>) -> Result<(), TemplateError> {
+    git_output(args, dir).map(|_| ())
+}
+
+/// Runs git without ever prompting for credentials and returns its stdout. Stderr is returned
+/// in the error when git exits with a failure status.
+fn git_output(args: &[&str], dir: Option<&Path>) -> Result<String, TemplateError> {
+    let mut command = process::Command::new("git");
+    command
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+
+    let output = command.output().map_err(|_e| TemplateError::ErrorExecutingGit)?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let errors = stderr
+        .lines()
+        .filter(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+        .collect::<Vec<&str>>();
+
+    // Progress and warnings are only noise when the errors can be told apart
+    let stderr = match errors.is_empty() {
+        true => stderr.trim().to_string(),
+        false => errors.join("\n"),
+    };
+
+    match stderr.is_empty() {
+        true => Err(TemplateError::GitCommandFailed(format!("exited with {}", output.status))),
+        false => Err(TemplateError::GitCommandFailed(stderr)),
     }
 }
 
-fn get_git_template_description(url: &str) -> Option<String> {
-    
-    if url.starts_with("https://github") || url.starts_with("http://github") { 
-        // TODO: Implement calling the GitHub API to get the description
+/// Strips or reinitialises the repository of a spawned template. Spawns of a subdirectory
+/// have no `.git` directory to keep, but can still get a fresh one.
+pub fn apply_history(dir: &Path, history: History) -> Result<(), TemplateError> {
+    if history == History::Keep || !dir.is_dir() {
+        return Ok(());
     }
-    
-    None
+
+    let git_dir = dir.join(".git");
+
+    if git_dir.exists() {
+        fs::remove_dir_all(&git_dir).map_err(|_e| TemplateError::IoError)?;
+    }
+
+    if history == History::Fresh {
+        // A repository without its first commit is of no use, so it isn't left behind
+        if let Err(e) = init_repository(dir) {
+            let _ = fs::remove_dir_all(&git_dir);
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Author used for the first commit of a fresh history when the user's git config has none.
+const FALLBACK_USER: [(&str, &str); 2] = [("user.name", "mkt"), ("user.email", "mkt@localhost")];
+
+/// Creates a repository with every file in a first commit, empty if there are no files.
+fn init_repository(dir: &Path) -> Result<(), TemplateError> {
+    run_git(&["init", "--quiet"], Some(dir))?;
+    run_git(&["add", "--all"], Some(dir))?;
+
+    // The author details are taken from the user's git config when it has them
+    let identity = FALLBACK_USER
+        .iter()
+        .filter(|(key, _)| git_output(&["config", "--get", key], Some(dir)).map_or(true, |value| value.trim().is_empty()))
+        .flat_map(|(key, value)| ["-c".to_string(), format!("{}={}", key, value)])
+        .collect::<Vec<String>>();
+
+    let mut args = identity.iter().map(String::as_str).collect::<Vec<&str>>();
+    args.extend(["commit", "--quiet", "--allow-empty", "--message", "Initial commit"]);
+
+    run_git(&args, Some(dir))
+}
+
+/// `file://` url of a local path, the path is made absolute first.
+fn file_url(path: &Path) -> String {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy();
+    // Canonical Windows paths are verbatim, e.g. \\?\C:\Users
+    let path = path.trim_start_matches(r"\\?\").replace('\\', "/");
+
+    match path.starts_with('/') {
+        true => format!("file://{}", path),
+        false => format!("file:///{}", path),
+    }
+}
+
+/// Only relative paths that stay inside the repository are valid.
+fn is_valid_subdir(subdir: &str) -> bool {
+    Path::new(subdir)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// First file, by name, at the top level of the directory with a txml or xml extension that
+/// is a valid TXML template.
+fn find_txml_file(dir: &Path) -> Option<PathBuf> {
+    let mut files = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && matches!(path.extension().and_then(|ext| ext.to_str()), Some("txml") | Some("xml"))
+        })
+        .collect::<Vec<PathBuf>>();
+
+    files.sort();
+    files.into_iter().find(TxmlStructure::validate_txml_file)
 }
 
+/// 64 bits FNV-1a. Unlike the std hashers, it is guaranteed to be stable between releases,
+/// which matters for the names of the mirrors.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// First paragraph of a README, skipping headings, badges and html.
+fn first_paragraph(readme: &str) -> Option<String> {
+    let paragraph = readme
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| {
+            line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("![")
+                || line.starts_with("[![")
+                || line.starts_with('<')
+                || line.starts_with("==")
+                || line.starts_with("--")
+        })
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    Some(paragraph).filter(|paragraph| !paragraph.is_empty())
+}
+
+/// Abbreviated or full commit hashes. Anything else is treated as a branch or a tag.
+fn is_commit_hash(git_ref: &str) -> bool {
+    (7..=40).contains(&git_ref.len()) && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_is_valid_subdir()
+    {
+        assert!(is_valid_subdir("templates/rust-service"));
+        assert!(is_valid_subdir("./templates/"));
+        assert!(!is_valid_subdir("/templates"));
+        assert!(!is_valid_subdir("templates/../../etc"));
+    }
+
+    #[test]
+    fn test_history_from_str()
+    {
+        assert_eq!("keep".parse::<History>().ok(), Some(History::Keep));
+        assert_eq!("strip".parse::<History>().ok(), Some(History::Strip));
+        assert_eq!("fresh".parse::<History>().ok(), Some(History::Fresh));
+        assert!("squash".parse::<History>().is_err());
+    }
+
+    #[test]
+    fn test_fnv1a_hash()
+    {
+        assert_eq!(fnv1a_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(fnv1a_hash(b"https://host/a.git"), fnv1a_hash(b"https://host/b.git"));
+    }
+
+    #[test]
+    fn test_first_paragraph()
+    {
+        let readme = "# Title\n\n[![CI](badge.svg)](ci)\n\nA tool that\ndoes things.\n\nMore text.";
+
+        assert_eq!(first_paragraph(readme), Some("A tool that does things.".to_string()));
+        assert_eq!(first_paragraph("# Only a title\n"), None);
+    }
+
+    #[test]
+    fn test_is_commit_hash()
+    {
+        assert!(is_commit_hash("3f2a9c1"));
+        assert!(is_commit_hash("3f2a9c1d0e4b5a6978877665544332211aabbccd"));
+        assert!(!is_commit_hash("v1.2.0"));
+        assert!(!is_commit_hash("main"));
+        assert!(!is_commit_hash("abc"));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::templates::data::TemplateData;
+use crate::templates::dir::DIR_TEMPLATE;
+use crate::templates::git::{self, GIT_TEMPLATE};
+use crate::templates::txml::TXML_TEMPLATE;
+use crate::templates::{get_available_templates, store, TemplateError, SAVE_TEMPLATES_DIR};
+use crate::CONFIG_DIR;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Broken entries are moved here by `doctor --fix`, so nothing is ever deleted.
+pub static QUARANTINE_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("quarantine"));
+
+/// A problem of the installation or a broken entry of the templates' dir.
+pub struct Problem {
+    pub name: String,
+    pub description: String,
+    /// Entry of the templates' dir, when its name isn't the problem's one.
+    path: Option<PathBuf>,
+    fix: Option<Fix>,
+}
+
+enum Fix {
+    /// Moves the entry to the quarantine dir.
+    Quarantine,
+    /// Rewrites the absolute payload path relative to the templates' dir.
+    Relativize,
+    /// Writes new data for the payload left in the template's directory.
+    RebuildData(&'static str),
+    /// Moves a payload saved by an older version without its data to its own template.
+    Adopt(&'static str),
+}
+
+impl Problem {
+    fn new(name: &str, description: String, fix: Option<Fix>) -> Self {
+        Self { name: name.to_string(), description, path: None, fix }
+    }
+
+    /// What `doctor --fix` does with the entry.
+    pub fn fix_description(&self) -> String {
+        match self.fix {
+            Some(Fix::Quarantine) => "moved to the quarantine".to_string(),
+            Some(Fix::Relativize) => "payload path made relative".to_string(),
+            Some(Fix::RebuildData(_)) => "data rebuilt from the payload".to_string(),
+            Some(Fix::Adopt(_)) => format!("adopted as the template {}", payload_owner(&self.name)),
+            None => "can't be fixed automatically".to_string(),
+        }
+    }
+
+    pub fn is_fixable(&self) -> bool {
+        self.fix.is_some()
+    }
+
+    /// Applies the fix. The store must be locked.
+    pub fn fix(&self) -> io::Result<()> {
+        match self.fix {
+            Some(Fix::Quarantine) => {
+                let path = self.path.clone().unwrap_or_else(|| SAVE_TEMPLATES_DIR.join(&self.name));
+                quarantine(&path).map(|_| ())
+            }
+            Some(Fix::Relativize) => {
+                let mut template_data = TemplateData::load(&self.name)?;
+                template_data.relativize();
+                template_data.save(&self.name)
+            }
+            Some(Fix::RebuildData(class)) => rebuild_data(&self.name, class),
+            Some(Fix::Adopt(class)) => adopt(&self.name, class),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fixes the broken entries of the templates' dir and returns them with the result of the fix.
+/// The store stays locked, so nothing changes between the check and the fix.
+pub fn fix_store() -> Result<Vec<(Problem, io::Result<()>)>, TemplateError> {
+    let _lock = store::lock()?;
+
+    Ok(check_store()
+        .into_iter()
+        .filter(Problem::is_fixable)
+        .map(|problem| {
+            let result = problem.fix();
+            (problem, result)
+        })
+        .collect())
+}
+
+/// Checks every entry of the templates' dir. Hidden entries belong to operations in progress
+/// and are left to the store's recovery.
+pub fn check_store() -> Vec<Problem> {
+    let entries = match fs::read_dir(SAVE_TEMPLATES_DIR.as_path()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return vec![Problem::new(".", format!("the templates' dir can't be read: {e:?}"), None)];
+        }
+    };
+
+    let mut problems = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|entry| match entry.file_name().into_string() {
+            Ok(name) => check_entry(&name, &entry.path()),
+            Err(file_name) => Some(Problem {
+                name: file_name.to_string_lossy().to_string(),
+                description: "the name isn't valid UTF-8".to_string(),
+                path: Some(entry.path()),
+                fix: Some(Fix::Quarantine),
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    problems.sort_by(|a, b| a.name.cmp(&b.name));
+    problems
+}
+
+/// Checks where the templates are kept and whether git can be run.
+pub fn check_installation() -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    match crate::mkt_home() {
+        Some(home) if home.is_relative() => problems.push(Problem::new(
+            "MKT_HOME",
+            format!("{} is relative, so the templates change with the current dir. Set it to an absolute path", home.display()),
+            None,
+        )),
+        None if crate::home_dir().is_none() => problems.push(Problem::new(
+            "MKT_HOME",
+            format!("neither MKT_HOME nor a home directory is set, so the templates are kept in {}, which may be cleaned. Set MKT_HOME", CONFIG_DIR.display()),
+            None,
+        )),
+        _ => {}
+    }
+
+    let lock_file = OpenOptions::new().create(true).truncate(false).write(true).open(CONFIG_DIR.join(store::LOCK_FILE));
+
+    if !CONFIG_DIR.is_dir() {
+        problems.push(Problem::new("MKT_HOME", format!("{} doesn't exist and couldn't be created", CONFIG_DIR.display()), None));
+    } else if let Err(e) = lock_file {
+        problems.push(Problem::new("MKT_HOME", format!("{} isn't writable: {e}", CONFIG_DIR.display()), None));
+    }
+
+    if git::git_version().is_none() {
+        problems.push(Problem::new(
+            "git",
+            "git isn't on PATH, so git templates can't be added, spawned or updated. Install git".to_string(),
+            None,
+        ));
+    }
+
+    problems
+}
+
+/// Checks what can only be told by reading the templates: TXML payloads that aren't valid and
+/// git remotes that can't be reached. Remotes are only checked if git can be run.
+pub fn check_templates() -> Vec<Problem> {
+    let git_installed = git::git_version().is_some();
+
+    get_available_templates()
+        .into_iter()
+        .filter(|(template_data, _)| !template_data.is_dangling())
+        .filter_map(|(template_data, name)| {
+            let template = template_data.to_template().ok()?;
+
+            match template_data.class() {
+                TXML_TEMPLATE if !template.validate() => Some(Problem::new(
+                    &name,
+                    format!("the TXML payload {} isn't valid. Fix it or add the template again", template_data.location()),
+                    None,
+                )),
+                GIT_TEMPLATE if git_installed && !template.validate() => Some(Problem::new(
+                    &name,
+                    format!("the remote {} isn't reachable. Check the url and the network or remove the template", template_data.location()),
+                    None,
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn check_entry(name: &str, path: &Path) -> Option<Problem> {
+    if let Some(problem) = check_orphaned_payload(name, path) {
+        return Some(problem);
+    }
+
+    if !path.is_dir() {
+        return Some(Problem::new(name, "isn't a template directory".to_string(), Some(Fix::Quarantine)));
+    }
+
+    let rebuild = rebuildable_class(name).map(Fix::RebuildData).unwrap_or(Fix::Quarantine);
+    let data_file = path.join(store::TEMPLATE_DATA_FILE);
+
+    if !data_file.is_file() {
+        return Some(Problem::new(name, format!("{} is missing", store::TEMPLATE_DATA_FILE), Some(rebuild)));
+    }
+
+    let template_data = match fs::read_to_string(&data_file) {
+        Ok(content) => match TemplateData::from_json(&content) {
+            Ok(template_data) => template_data,
+            Err(e) => {
+                return Some(Problem::new(name, format!("{} is invalid: {e}", store::TEMPLATE_DATA_FILE), Some(rebuild)))
+            }
+        },
+        Err(e) => {
+            return Some(Problem::new(name, format!("{} can't be read: {e}", store::TEMPLATE_DATA_FILE), None));
+        }
+    };
+
+    if let Err(e) = template_data.to_template() {
+        return Some(Problem::new(name, format!("{:?}", e).trim_end_matches('.').to_string(), Some(Fix::Quarantine)));
+    }
+
+    if template_data.is_dangling() {
+        let mut relativized = template_data.clone();
+        let fix = match relativized.relativize() && !relativized.is_dangling() {
+            true => Fix::Relativize,
+            false => Fix::Quarantine,
+        };
+
+        return Some(Problem::new(
+            name,
+            format!("the payload {} doesn't exist", template_data.payload_path().display()),
+            Some(fix),
+        ));
+    }
+
+    None
+}
+
+/// A `<name>.txml` or `<name>.dir` payload saved by an older version whose data file is gone,
+/// so the layout migration left it behind.
+fn check_orphaned_payload(name: &str, path: &Path) -> Option<Problem> {
+    let class = match name.rsplit_once('.') {
+        Some((_, "txml")) if path.is_file() => TXML_TEMPLATE,
+        Some((_, "dir")) if path.is_dir() && !path.join(store::TEMPLATE_DATA_FILE).exists() => DIR_TEMPLATE,
+        _ => return None,
+    };
+
+    let owner = payload_owner(name);
+    let fix = match owner.is_empty() || store::template_dir(owner).exists() {
+        true => Fix::Quarantine,
+        false => Fix::Adopt(class),
+    };
+
+    Some(Problem::new(name, format!("orphaned {} payload without data", class), Some(fix)))
+}
+
+/// Name of the template an orphaned payload belonged to.
+fn payload_owner(name: &str) -> &str {
+    name.rsplit_once('.').map(|(owner, _)| owner).unwrap_or(name)
+}
+
+fn adopt(name: &str, class: &'static str) -> io::Result<()> {
+    let owner = payload_owner(name);
+    let payload = store::payload_dir(owner);
+
+    match class {
+        TXML_TEMPLATE => {
+            fs::create_dir_all(&payload)?;
+            fs::rename(SAVE_TEMPLATES_DIR.join(name), payload.join(store::TXML_PAYLOAD_FILE))?;
+        }
+        _ => {
+            fs::create_dir_all(store::template_dir(owner))?;
+            fs::rename(SAVE_TEMPLATES_DIR.join(name), &payload)?;
+        }
+    }
+
+    rebuild_data(owner, class)
+}
+
+/// Class of the payload left in the template's directory, if any.
+fn rebuildable_class(name: &str) -> Option<&'static str> {
+    let payload = store::payload_dir(name);
+
+    if payload.join(store::TXML_PAYLOAD_FILE).is_file() {
+        Some(TXML_TEMPLATE)
+    } else if payload.is_dir() {
+        Some(DIR_TEMPLATE)
+    } else {
+        None
+    }
+}
+
+fn rebuild_data(name: &str, class: &str) -> io::Result<()> {
+    let data_path = match class {
+        TXML_TEMPLATE => store::txml_payload_path(name),
+        _ => store::dir_payload_path(name),
+    };
+
+    let mut template_data = TemplateData::new(class, &data_path);
+    template_data.update_checksum();
+    template_data.save(name)
+}
+
+/// Moves an entry of the templates' dir to the quarantine dir, under a new name if another
+/// entry with the same name is already there.
+fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(QUARANTINE_DIR.as_path())?;
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut target = QUARANTINE_DIR.join(name.as_ref());
+    let mut copy = 1;
+
+    while target.exists() {
+        target = QUARANTINE_DIR.join(format!("{}.{}", name, copy));
+        copy += 1;
+    }
+
+    fs::rename(path, &target)?;
+
+    Ok(target)
+}
@@ -0,0 +1,260 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+pub const MKT_IGNORE_FILE: &str = ".mktignore";
+pub const GIT_IGNORE_FILE: &str = ".gitignore";
+
+/// Set of gitignore-like rules used to skip entries when a directory is added as a template.
+/// Supports comments, `!` negation, trailing `/` for directories only, anchored patterns and
+/// the `*`, `**`, `?` and `[...]` wildcards. The last matching rule wins.
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+struct IgnoreRule {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Builds the rules for the source dir: its `.mktignore`, its `.gitignore` when
+    /// `use_gitignore` is set and then the extra patterns, in that order.
+    pub fn for_dir(dir: &Path, use_gitignore: bool, extra: &[&str]) -> Self {
+        let mut rules = Self::new();
+
+        rules.add_file(&dir.join(MKT_IGNORE_FILE));
+
+        if use_gitignore {
+            rules.add_file(&dir.join(GIT_IGNORE_FILE));
+        }
+
+        extra.iter().for_each(|pattern| rules.add(pattern));
+
+        rules
+    }
+
+    pub fn add_file(&mut self, file: &Path) {
+        if let Ok(content) = fs::read_to_string(file) {
+            content.lines().for_each(|line| self.add(line));
+        }
+    }
+
+    pub fn add(&mut self, line: &str) {
+        let line = line.trim_end();
+
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, line),
+        };
+
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+
+        if pattern.is_empty() {
+            return;
+        }
+
+        self.rules.push(IgnoreRule {
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        });
+    }
+
+    /// Patterns in the order they are applied, as they would be written in an ignore file.
+    pub fn patterns(&self) -> Vec<String> {
+        self.rules.iter().map(IgnoreRule::to_string).collect()
+    }
+
+    /// Checks if a path relative to the template root must be skipped.
+    pub fn is_ignored(&self, relative_path: &Path, is_dir: bool) -> bool {
+        let path = relative_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let name = path.rsplit('/').next().unwrap_or(path.as_str());
+        let mut ignored = false;
+
+        for rule in self.rules.iter() {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+
+            let matches = if rule.anchored {
+                glob_match(rule.pattern.as_bytes(), path.as_bytes())
+            } else {
+                glob_match(rule.pattern.as_bytes(), name.as_bytes())
+            };
+
+            if matches {
+                ignored = !rule.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+impl Display for IgnoreRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.negated {
+            write!(f, "!")?;
+        }
+
+        if self.anchored && !self.pattern.contains('/') {
+            write!(f, "/")?;
+        }
+
+        write!(f, "{}", self.pattern)?;
+
+        if self.dir_only {
+            write!(f, "/")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = &pattern[2..];
+
+            // "**/" can also match zero directories
+            if rest.first() == Some(&b'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == b'/' {
+                    break;
+                }
+            }
+
+            false
+        }
+        Some(b'?') => match text.first() {
+            Some(c) if *c != b'/' => glob_match(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some(b'[') => match (text.first(), pattern.iter().position(|c| *c == b']')) {
+            (Some(c), Some(end)) if end > 1 && *c != b'/' => {
+                class_match(&pattern[1..end], *c) && glob_match(&pattern[end + 1..], &text[1..])
+            }
+            _ => false,
+        },
+        Some(b'\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+fn class_match(class: &[u8], c: u8) -> bool {
+    let (negated, class) = match class.first() {
+        Some(b'!') | Some(b'^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+
+    let mut i = 0;
+    let mut matched = false;
+
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == b'-' {
+            matched |= class[i] <= c && c <= class[i + 2];
+            i += 3;
+        } else {
+            matched |= class[i] == c;
+            i += 1;
+        }
+    }
+
+    matched != negated
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rules(patterns: &[&str]) -> IgnoreRules
+    {
+        let mut rules = IgnoreRules::new();
+        patterns.iter().for_each(|p| rules.add(p));
+        rules
+    }
+
+    #[test]
+    fn test_unanchored_patterns()
+    {
+        let rules = rules(&["target/", "*.log", "node_modules"]);
+
+        assert!(rules.is_ignored(&PathBuf::from("target"), true));
+        assert!(!rules.is_ignored(&PathBuf::from("target"), false));
+        assert!(rules.is_ignored(&PathBuf::from("crates/a/target"), true));
+        assert!(rules.is_ignored(&PathBuf::from("logs/out.log"), false));
+        assert!(rules.is_ignored(&PathBuf::from("web/node_modules"), true));
+        assert!(!rules.is_ignored(&PathBuf::from("src/main.rs"), false));
+    }
+
+    #[test]
+    fn test_anchored_and_negated_patterns()
+    {
+        let rules = rules(&["# comment", "/build", "docs/*.md", "!docs/README.md", "**/gen/**"]);
+
+        assert!(rules.is_ignored(&PathBuf::from("build"), true));
+        assert!(!rules.is_ignored(&PathBuf::from("src/build"), true));
+        assert!(rules.is_ignored(&PathBuf::from("docs/guide.md"), false));
+        assert!(!rules.is_ignored(&PathBuf::from("docs/README.md"), false));
+        assert!(!rules.is_ignored(&PathBuf::from("docs/api/guide.md"), false));
+        assert!(rules.is_ignored(&PathBuf::from("a/b/gen/file.rs"), false));
+        assert!(rules.is_ignored(&PathBuf::from("gen/file.rs"), false));
+    }
+
+    #[test]
+    fn test_wildcards()
+    {
+        assert!(glob_match(b"file?.[ch]", b"file1.c"));
+        assert!(!glob_match(b"file?.[ch]", b"file1.rs"));
+        assert!(glob_match(b"[!a-c]*", b"dist"));
+        assert!(!glob_match(b"[!a-c]*", b"build"));
+        assert!(!glob_match(b"*.rs", b"src/main.rs"));
+    }
+
+    #[test]
+    fn test_patterns_round_trip()
+    {
+        let rules = rules(&["/build", "target/", "!keep.log", "docs/*.md"]);
+
+        assert_eq!(rules.patterns(), vec!["/build", "target/", "!keep.log", "docs/*.md"]);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::templates::TemplateError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use txml_processor::txml_elements::Variable;
+
+/// Sidecar file stored inside a directory template with the data the directory itself can't hold.
+pub const MANIFEST_FILE: &str = "mkt.toml";
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Manifest
+{
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    variables: Vec<ManifestVariable>,
+}
+
+/// Same as a TXML Variable: without value it is asked when the template is spawned.
+#[derive(Serialize, Deserialize)]
+pub struct ManifestVariable
+{
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    value: String,
+}
+
+impl Manifest
+{
+    /// Loads the manifest of the directory, if it has one. A `mkt.toml` that isn't TOML or
+    /// has neither a description nor variables belongs to the project, it isn't a manifest.
+    pub fn load(dir: &Path) -> Result<Option<Self>, TemplateError>
+    {
+        let file = dir.join(MANIFEST_FILE);
+
+        if !file.is_file() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(file).map_err(|_e| TemplateError::IoError)?;
+
+        match content.parse::<toml::Table>() {
+            Ok(table) if table.contains_key("description") || table.contains_key("variables") => {
+                Self::from_toml(content.as_str()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks if the directory has a `mkt.toml` that isn't a manifest, see `load`.
+    pub fn is_foreign(dir: &Path) -> bool
+    {
+        dir.join(MANIFEST_FILE).is_file() && matches!(Self::load(dir), Ok(None))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, TemplateError>
+    {
+        toml::from_str(content).map_err(|_e| TemplateError::InvalidManifest)
+    }
+
+    pub fn description(&self) -> Option<&str>
+    {
+        self.description.as_deref()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), TemplateError>
+    {
+        let content = toml::to_string_pretty(self).map_err(|_e| TemplateError::InvalidManifest)?;
+
+        fs::write(dir.join(MANIFEST_FILE), content).map_err(|_e| TemplateError::IoError)
+    }
+
+    pub fn variables(&self) -> Vec<Variable>
+    {
+        self.variables
+            .iter()
+            .map(|variable| Variable::with_value(&variable.name, &variable.value))
+            .collect()
+    }
+
+    /// Declares a variable without value unless it is already declared.
+    pub fn declare_variable(&mut self, name: &str)
+    {
+        if self.variables.iter().all(|variable| variable.name != name) {
+            self.variables.push(ManifestVariable {
+                name: name.to_string(),
+                value: String::new(),
+            });
+        }
+    }
+
+    /// Values of every variable, the given ones first. The rest keep their own value or are
+    /// asked in the stdin.
+    pub fn resolve_variables(&self, values: &HashMap<String, String>) -> Vec<(String, String)>
+    {
+        self.variables()
+            .iter()
+            .map(|variable| {
+                let value = match values.get(variable.get_name()) {
+                    Some(value) => value.clone(),
+                    None => variable.resolve_value(),
+                };
+
+                (variable.get_name().to_string(), value)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip()
+    {
+        let mut manifest: Manifest = toml::from_str(
+            r#"
+            [[variables]]
+            name = "author"
+            value = "Acme"
+            "#,
+        )
+        .unwrap();
+
+        manifest.declare_variable("project_name");
+        manifest.declare_variable("author");
+
+        let content = toml::to_string_pretty(&manifest).unwrap();
+        let manifest: Manifest = toml::from_str(content.as_str()).unwrap();
+        let variables = manifest.variables();
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables[0].get_name(), "author");
+        assert_eq!(variables[0].get_value(), "Acme");
+        assert_eq!(variables[1].get_name(), "project_name");
+        assert_eq!(variables[1].get_value(), "");
+    }
+
+    #[test]
+    fn test_foreign_manifest()
+    {
+        let dir = std::env::temp_dir().join(format!("mkt-manifest-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join(MANIFEST_FILE), "[package]\nname = \"app\"\n").unwrap();
+        assert!(Manifest::load(&dir).unwrap().is_none());
+        assert!(Manifest::is_foreign(&dir));
+
+        fs::write(dir.join(MANIFEST_FILE), "not = [toml").unwrap();
+        assert!(Manifest::load(&dir).unwrap().is_none());
+
+        fs::write(dir.join(MANIFEST_FILE), "description = \"A starter\"\n").unwrap();
+        assert_eq!(Manifest::load(&dir).unwrap().unwrap().description(), Some("A starter"));
+        assert!(!Manifest::is_foreign(&dir));
+
+        fs::write(dir.join(MANIFEST_FILE), "variables = \"author\"\n").unwrap();
+        assert!(Manifest::load(&dir).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
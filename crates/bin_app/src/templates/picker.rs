@@ -0,0 +1,145 @@
+use crate::templates::data::TemplateData;
+use crate::templates::search;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use txml_processor::txml_elements::Variable;
+
+/// The planned tree shows at most this many lines before the spawn is confirmed, then one more
+/// line with the count of the rest.
+const MAX_TREE_LINES: usize = 40;
+
+/// Shows the templates with their descriptions and lets the user filter them by text until one
+/// is picked by number. Returns None if the user cancels with an empty answer.
+pub fn pick_template(templates: Vec<(TemplateData, String)>) -> Option<(TemplateData, String)> {
+    let mut shown = templates.iter().collect::<Vec<_>>();
+
+    loop {
+        eprintln!();
+
+        for (i, (template_data, name)) in shown.iter().enumerate() {
+            let description = match template_data.to_template() {
+                Ok(template) => template.get_description(),
+                Err(e) => format!("{:?}", e),
+            };
+
+            eprintln!("{:>3}) {}: {}", i + 1, name, description);
+        }
+
+        if shown.is_empty() {
+            eprintln!("No templates match.");
+        }
+
+        let answer = ask("Number of the template, text to filter them or nothing to cancel: ")?;
+
+        if answer.is_empty() {
+            return None;
+        }
+
+        match answer.parse::<usize>() {
+            Ok(number) if (1..=shown.len()).contains(&number) => return Some(shown[number - 1].clone()),
+            _ => {
+                shown = templates
+                    .iter()
+                    .filter(|(template_data, name)| search::matches(template_data, name, &answer))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Asks for the value of every variable that wasn't given, offering its own value as the
+/// default.
+pub fn ask_variables(variables: &[Variable], given: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+
+    for variable in variables.iter().filter(|variable| !given.contains_key(variable.get_name())) {
+        let prompt = match variable.get_value() {
+            "" => format!("{}: ", variable.get_name()),
+            default => format!("{} [{}]: ", variable.get_name(), default),
+        };
+
+        let value = match ask(&prompt) {
+            Some(value) if !value.is_empty() => value,
+            _ => variable.get_value().to_string(),
+        };
+
+        values.push((variable.get_name().to_string(), value));
+    }
+
+    values
+}
+
+/// Shows the tree spawned in the staging directory and asks whether to write it in `target`.
+/// Any answer but yes cancels the spawn.
+pub fn confirm_spawn(staging: &Path, target: &Path) -> bool {
+    let mut lines = Vec::new();
+    tree_lines(staging, 0, &mut lines);
+
+    eprintln!();
+    eprintln!("The template will be spawned in {}:", target.display());
+
+    for line in lines.iter().take(MAX_TREE_LINES) {
+        eprintln!("{}", line);
+    }
+
+    if lines.len() > MAX_TREE_LINES {
+        eprintln!("  ... and {} more", lines.len() - MAX_TREE_LINES);
+    }
+
+    let existing = fs::read_dir(staging)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| target.join(entry.file_name()).symlink_metadata().is_ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if !existing.is_empty() {
+        eprintln!("Already exist and will be kept: {}", existing.join(", "));
+    }
+
+    matches!(ask("Spawn it? [y/N] ").as_deref(), Some("y" | "Y" | "yes"))
+}
+
+fn tree_lines(dir: &Path, depth: usize, lines: &mut Vec<String>) {
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let indent = "  ".repeat(depth + 1);
+
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => {
+                let target = fs::read_link(entry.path()).unwrap_or_default();
+                lines.push(format!("{}{} -> {}", indent, name, target.display()));
+            }
+            Ok(file_type) if file_type.is_dir() => {
+                lines.push(format!("{}{}/", indent, name));
+                tree_lines(&entry.path(), depth + 1, lines);
+            }
+            _ => lines.push(format!("{}{}", indent, name)),
+        }
+    }
+}
+
+/// Prompts on the stderr, like the rest of the picker, and reads a trimmed answer from the
+/// stdin. None if it is closed.
+fn ask(prompt: &str) -> Option<String> {
+    eprint!("{}", prompt);
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+
+    match io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim().to_string()),
+    }
+}
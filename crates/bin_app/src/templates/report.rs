@@ -0,0 +1,359 @@
+use crate::templates::data::TemplateData;
+use crate::templates::dir::DIR_TEMPLATE;
+use crate::templates::git::GIT_TEMPLATE;
+use crate::templates::manifest::{Manifest, MANIFEST_FILE};
+use crate::templates::txml::{self, TXML_TEMPLATE};
+use crate::templates::{declared_variables, TemplateError};
+use serde::Serialize;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Machine-readable output of `list`, `info` and `validate`. Without it they print free-form
+/// text, which may change between versions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "table" => Ok(OutputFormat::Table),
+            _ => Err(TemplateError::InvalidOutputFormat),
+        }
+    }
+}
+
+/// What `list` reports of a template. The field names are stable, scripts rely on them.
+#[derive(Serialize)]
+pub struct TemplateReport {
+    pub name: String,
+    pub class: String,
+    pub source: Option<String>,
+    pub description: String,
+    /// Version of the TXML Metadata.
+    pub version: Option<String>,
+    pub tags: Vec<String>,
+    /// Names of the variables asked when the template is spawned, if they can be read without
+    /// the network.
+    pub variables: Vec<String>,
+}
+
+impl TemplateReport {
+    pub fn new(template_data: &TemplateData, name: &str) -> Self {
+        let description = match template_data.to_template() {
+            Ok(template) => template.get_description(),
+            Err(e) => format!("{:?}", e),
+        };
+
+        let version = match template_data.class() {
+            TXML_TEMPLATE => txml::read_metadata(&template_data.payload_path())
+                .map(|metadata| metadata.version)
+                .filter(|version| !version.is_empty()),
+            _ => None,
+        };
+
+        Self {
+            name: name.to_string(),
+            class: template_data.class().to_string(),
+            source: template_data.source().map(String::from),
+            description,
+            version,
+            tags: template_data.tags().to_vec(),
+            variables: declared_variables(template_data)
+                .iter()
+                .map(|variable| variable.get_name().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// What `info` reports of a template, the fields of `list` first. Dates are seconds since the
+/// Unix epoch.
+#[derive(Serialize)]
+pub struct InfoReport {
+    #[serde(flatten)]
+    pub template: TemplateReport,
+    pub path: String,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub last_spawned: Option<u64>,
+    pub checksum: Option<String>,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    pub subdir: Option<String>,
+    pub history: Option<String>,
+    pub ignore: Vec<String>,
+}
+
+impl InfoReport {
+    pub fn new(template_data: &TemplateData, name: &str) -> Self {
+        Self {
+            template: TemplateReport::new(template_data, name),
+            path: template_data.location(),
+            created_at: template_data.created_at(),
+            updated_at: template_data.updated_at(),
+            last_spawned: template_data.last_spawned(),
+            checksum: template_data.checksum().map(String::from),
+            git_ref: template_data.git_ref().map(String::from),
+            subdir: template_data.subdir().map(String::from),
+            history: template_data.history().map(String::from),
+            ignore: template_data.ignore().to_vec(),
+        }
+    }
+}
+
+/// What `validate` reports of a template.
+#[derive(Serialize)]
+pub struct ValidationReport {
+    pub name: String,
+    pub valid: bool,
+    pub problems: Vec<String>,
+}
+
+impl ValidationReport {
+    /// Checks that the template can be spawned. Git remotes are reached through the network.
+    pub fn new(template_data: Option<&TemplateData>, name: &str) -> Self {
+        let problems = match template_data {
+            Some(template_data) => problems(template_data),
+            None => vec!["the template doesn't exist".to_string()],
+        };
+
+        Self { name: name.to_string(), valid: problems.is_empty(), problems }
+    }
+}
+
+fn problems(template_data: &TemplateData) -> Vec<String> {
+    let template = match template_data.to_template() {
+        Ok(template) => template,
+        Err(e) => return vec![format!("{:?}", e).trim_end_matches('.').to_string()],
+    };
+
+    if template_data.is_dangling() {
+        return vec![format!("the payload {} doesn't exist", template_data.location())];
+    }
+
+    let mut problems = Vec::new();
+
+    if !template.validate() {
+        problems.push(match template_data.class() {
+            TXML_TEMPLATE => format!("the TXML payload {} isn't valid", template_data.location()),
+            GIT_TEMPLATE => format!("the remote {} isn't reachable", template_data.location()),
+            _ => format!("the payload {} isn't a directory", template_data.location()),
+        });
+    }
+
+    if template_data.class() == DIR_TEMPLATE && Manifest::load(&template_data.payload_path()).is_err() {
+        problems.push(format!("the {} manifest isn't valid", MANIFEST_FILE));
+    }
+
+    problems
+}
+
+/// Writes a report, or a list of them, in the format.
+pub fn render<T: Serialize + ?Sized>(report: &T, format: OutputFormat) -> Result<String, TemplateError> {
+    // The values keep the order of the fields, so the YAML keys and the columns follow it
+    let value = serde_json::to_value(report).map_err(|_e| TemplateError::IoError)?;
+
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(&value).map_err(|_e| TemplateError::IoError),
+        OutputFormat::Yaml => Ok(yaml_lines(&value).join("\n") + "\n"),
+        OutputFormat::Table => Ok(table(&value)),
+    }
+}
+
+/// A YAML block of the value. The items of a list are not indented under their key.
+fn yaml_lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) if !items.is_empty() => items
+            .iter()
+            .flat_map(|item| {
+                yaml_lines(item)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, line)| format!("{}{}", if i == 0 { "- " } else { "  " }, line))
+            })
+            .collect(),
+        Value::Object(fields) if !fields.is_empty() => fields
+            .iter()
+            .flat_map(|(field, value)| {
+                // The keys are the fields of the reports, they never need quotes
+                match value {
+                    Value::Array(items) if !items.is_empty() => {
+                        [format!("{}:", field)].into_iter().chain(yaml_lines(value)).collect::<Vec<_>>()
+                    }
+                    Value::Object(fields) if !fields.is_empty() => [format!("{}:", field)]
+                        .into_iter()
+                        .chain(yaml_lines(value).into_iter().map(|line| format!("  {}", line)))
+                        .collect(),
+                    _ => vec![format!("{}: {}", field, yaml_scalar(value))],
+                }
+            })
+            .collect(),
+        _ => vec![yaml_scalar(value)],
+    }
+}
+
+/// Strings are always quoted as in JSON, which YAML reads the same, so none of them is read as
+/// something else, e.g. `no` or `2024-01-01`.
+fn yaml_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Array(_) => "[]".to_string(),
+        Value::Object(_) => "{}".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A list of reports gets a row each and a column per field, a single report a row per field.
+fn table(value: &Value) -> String {
+    let rows = match value {
+        Value::Array(reports) => {
+            let headers = match reports.first() {
+                Some(Value::Object(fields)) => fields.keys().cloned().collect::<Vec<_>>(),
+                _ => Vec::new(),
+            };
+
+            let mut rows = vec![headers.iter().map(|header| header.to_uppercase()).collect::<Vec<_>>()];
+            rows.extend(reports.iter().map(|report| match report {
+                Value::Object(fields) => fields.values().map(cell).collect(),
+                other => vec![cell(other)],
+            }));
+            rows
+        }
+        Value::Object(fields) => fields.iter().map(|(field, value)| vec![field.clone(), cell(value)]).collect(),
+        other => vec![vec![cell(other)]],
+    };
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..columns)
+        .map(|column| rows.iter().filter_map(|row| row.get(column)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) if value.is_empty() => "-".to_string(),
+        Value::String(value) => value.replace('\n', " "),
+        Value::Array(values) if values.is_empty() => "-".to_string(),
+        Value::Array(values) => values.iter().map(cell).collect::<Vec<_>>().join(", "),
+        Value::Object(_) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> TemplateReport {
+        TemplateReport {
+            name: "starter".to_string(),
+            class: "git".to_string(),
+            source: None,
+            description: "A starter".to_string(),
+            version: Some("1.0.0".to_string()),
+            tags: vec!["rust".to_string(), "cli".to_string()],
+            variables: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("yaml".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
+        assert_eq!("table".parse::<OutputFormat>().unwrap(), OutputFormat::Table);
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_render_json_and_yaml() {
+        let json = render(&[report()], OutputFormat::Json).unwrap();
+        let parsed = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+        assert_eq!(parsed[0]["name"], "starter");
+        assert_eq!(parsed[0]["class"], "git");
+        assert_eq!(parsed[0]["source"], serde_json::Value::Null);
+        assert_eq!(parsed[0]["description"], "A starter");
+        assert_eq!(parsed[0]["version"], "1.0.0");
+        assert_eq!(parsed[0]["tags"], serde_json::json!(["rust", "cli"]));
+        assert_eq!(parsed[0]["variables"], serde_json::json!([]));
+
+        let yaml = render(&report(), OutputFormat::Yaml).unwrap();
+
+        assert!(yaml.starts_with("name: \"starter\"\nclass: \"git\"\nsource: null\n"));
+        assert!(yaml.contains("tags:\n- \"rust\"\n- \"cli\"\n"));
+        assert!(yaml.ends_with("variables: []\n"));
+
+        let yaml = render(&[report()], OutputFormat::Yaml).unwrap();
+
+        assert!(yaml.starts_with("- name: \"starter\"\n  class: \"git\"\n"));
+        assert!(yaml.contains("  tags:\n  - \"rust\"\n"));
+    }
+
+    #[test]
+    fn test_yaml_scalars() {
+        let scalar = |text: &str| yaml_scalar(&Value::String(text.to_string()));
+
+        assert_eq!(scalar("A starter"), r#""A starter""#);
+        assert_eq!(scalar("gh:org/repo"), r#""gh:org/repo""#);
+        assert_eq!(scalar(""), r#""""#);
+        assert_eq!(scalar("key: value"), r#""key: value""#);
+        assert_eq!(scalar("- item"), r#""- item""#);
+        assert_eq!(scalar(r#"say "hi" \ bye"#), r#""say \"hi\" \\ bye""#);
+
+        for ambiguous in ["y", "n", "yes", "off", "~", "null", "2024-01-01", "1.0", "1e3", "0x1F", "0o17"] {
+            assert_eq!(scalar(ambiguous), format!("\"{}\"", ambiguous));
+        }
+
+        assert_eq!(scalar("two\nlines"), r#""two\nlines""#);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = render(&[report()], OutputFormat::Table).unwrap();
+        let lines = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "NAME     CLASS  SOURCE  DESCRIPTION  VERSION  TAGS       VARIABLES");
+        assert_eq!(lines[1], "starter  git    -       A starter    1.0.0    rust, cli  -");
+
+        let table = render(&report(), OutputFormat::Table).unwrap();
+
+        assert!(table.starts_with("name         starter\nclass        git\n"));
+    }
+
+    #[test]
+    fn test_validation_report() {
+        let report = ValidationReport::new(None, "missing");
+
+        assert!(!report.valid);
+        assert_eq!(report.problems, ["the template doesn't exist"]);
+
+        let template_data = TemplateData::new("svn", "https://host/repo");
+        let report = ValidationReport::new(Some(&template_data), "svn");
+
+        assert!(!report.valid);
+        assert!(report.problems[0].starts_with("Unknown template class svn"));
+    }
+}
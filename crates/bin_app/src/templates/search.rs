@@ -0,0 +1,135 @@
+use crate::templates::data::TemplateData;
+use crate::templates::dir::DIR_TEMPLATE;
+use crate::templates::manifest::Manifest;
+use crate::templates::txml::{self, TXML_TEMPLATE};
+use crate::templates::TemplateError;
+use std::str::FromStr;
+
+/// Order of the templates listed by `list` and `search`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Name,
+    Created,
+    Updated,
+    Spawned,
+}
+
+impl FromStr for SortOrder {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(SortOrder::Name),
+            "created" => Ok(SortOrder::Created),
+            "updated" => Ok(SortOrder::Updated),
+            "spawned" => Ok(SortOrder::Spawned),
+            _ => Err(TemplateError::InvalidSortOrder),
+        }
+    }
+}
+
+/// Sorts by name, or newest first by the date. Templates that were never spawned go last.
+pub fn sort(templates: &mut [(TemplateData, String)], order: SortOrder) {
+    templates.sort_by(|(a, a_name), (b, b_name)| {
+        let by_date = match order {
+            SortOrder::Name => std::cmp::Ordering::Equal,
+            SortOrder::Created => b.created_at().cmp(&a.created_at()),
+            SortOrder::Updated => b.updated_at().cmp(&a.updated_at()),
+            SortOrder::Spawned => b.last_spawned().cmp(&a.last_spawned()),
+        };
+
+        by_date.then_with(|| a_name.cmp(b_name))
+    });
+}
+
+/// Tags are compared without case, so they are kept in lowercase.
+pub fn normalize_tag(tag: &str) -> Result<String, TemplateError> {
+    let tag = tag.trim().to_lowercase();
+
+    match !tag.is_empty() && !tag.contains(',') && !tag.contains(char::is_whitespace) {
+        true => Ok(tag),
+        false => Err(TemplateError::InvalidTag(tag)),
+    }
+}
+
+/// Checks if the template has every one of the tags.
+pub fn has_tags(template_data: &TemplateData, tags: &[&str]) -> bool {
+    tags.iter()
+        .all(|tag| template_data.tags().iter().any(|own| own.eq_ignore_ascii_case(tag.trim())))
+}
+
+/// Checks if the name, description, tags or author of the template contain the text, without
+/// case. The author is only known for TXML templates.
+pub fn matches(template_data: &TemplateData, name: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    let contains = |field: &str| field.to_lowercase().contains(&text);
+
+    contains(name)
+        || template_data.tags().iter().any(|tag| contains(tag))
+        || searchable_fields(template_data).iter().any(|field| contains(field))
+}
+
+/// Description and author written by the template's author. The generic descriptions shown
+/// for templates without one are left out, they would match every template of the class.
+fn searchable_fields(template_data: &TemplateData) -> Vec<String> {
+    match template_data.class() {
+        TXML_TEMPLATE => txml::read_metadata(&template_data.payload_path())
+            .map(|metadata| vec![metadata.description, metadata.author])
+            .unwrap_or_default(),
+        DIR_TEMPLATE => Manifest::load(&template_data.payload_path())
+            .ok()
+            .flatten()
+            .and_then(|manifest| manifest.description().map(String::from))
+            .into_iter()
+            .collect(),
+        _ => template_data.description().map(String::from).into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(tags: &[&str], created_at: u64) -> TemplateData {
+        let mut template_data =
+            TemplateData::from_json(&format!(r#"{{"class": "git", "data_path": "", "created_at": {}}}"#, created_at))
+                .unwrap();
+        template_data.set_tags(tags.iter().map(|tag| tag.to_string()).collect());
+        template_data.set_description(Some("A starter".to_string()));
+        template_data
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut templates = vec![
+            (template(&[], 1), "b".to_string()),
+            (template(&[], 2), "c".to_string()),
+            (template(&[], 1), "a".to_string()),
+        ];
+
+        sort(&mut templates, SortOrder::Name);
+        assert_eq!(templates.iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+
+        sort(&mut templates, SortOrder::Created);
+        assert_eq!(templates.iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>(), ["c", "a", "b"]);
+
+        assert!("size".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(normalize_tag(" Rust ").unwrap(), "rust");
+        assert!(normalize_tag("").is_err());
+        assert!(normalize_tag("rust cli").is_err());
+        assert!(normalize_tag("rust,cli").is_err());
+
+        let template_data = template(&["rust", "cli"], 0);
+
+        assert!(has_tags(&template_data, &["RUST"]));
+        assert!(has_tags(&template_data, &["rust", "cli"]));
+        assert!(!has_tags(&template_data, &["rust", "web"]));
+        assert!(matches(&template_data, "starter", "CL"));
+        assert!(matches(&template_data, "starter", "art"));
+        assert!(!matches(&template_data, "starter", "web"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::config::Config;
+use crate::templates::archive::ArchiveFormat;
+use crate::templates::TemplateError;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Kind of template a source is added as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceType {
+    Git,
+    Dir,
+    Txml,
+    Archive,
+}
+
+impl FromStr for SourceType {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "git" => Ok(SourceType::Git),
+            "dir" => Ok(SourceType::Dir),
+            "txml" => Ok(SourceType::Txml),
+            "archive" => Ok(SourceType::Archive),
+            _ => Err(TemplateError::InvalidSourceType),
+        }
+    }
+}
+
+impl Display for SourceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceType::Git => write!(f, "git"),
+            SourceType::Dir => write!(f, "dir"),
+            SourceType::Txml => write!(f, "txml"),
+            SourceType::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+/// Source of a template once its shorthand is expanded and its type known.
+pub struct Source {
+    pub source_type: SourceType,
+    pub location: String,
+}
+
+/// Works out what a source given to `add` is. Shorthands (`gh:org/repo`) are expanded first,
+/// then urls and scp-like addresses are git repositories. Local paths are git repositories
+/// when they are bare repositories, working copies or end with `.git`, directories when they
+/// are any other directory, archives when they are zip or tar files and TXML templates when
+/// they are any other file. An explicit type skips the detection but the source must still be
+/// usable as that type.
+pub fn resolve(source: &str, explicit: Option<SourceType>) -> Result<Source, TemplateError> {
+    resolve_with(source, explicit, &Config::load().shorthands())
+}
+
+fn resolve_with(
+    source: &str,
+    explicit: Option<SourceType>,
+    shorthands: &HashMap<String, String>,
+) -> Result<Source, TemplateError> {
+    let source = expand_shorthand(source, shorthands);
+    let local = is_local_path(&source);
+    let path = Path::new(&source);
+
+    let source_type = match explicit {
+        Some(source_type) => source_type,
+        None if !local => SourceType::Git,
+        None if path.is_dir() && (is_bare_repository(path) || path.join(".git").exists()) => SourceType::Git,
+        None if path.is_dir() => SourceType::Dir,
+        None if path.is_file() && ArchiveFormat::from_path(path).is_some() => SourceType::Archive,
+        None if path.is_file() => SourceType::Txml,
+        None if source.ends_with(".git") => SourceType::Git,
+        None => return Err(TemplateError::InvalidPath),
+    };
+
+    let valid = match source_type {
+        SourceType::Git => !local || path.exists(),
+        SourceType::Dir => path.is_dir(),
+        SourceType::Txml => path.is_file(),
+        SourceType::Archive => path.is_file() && ArchiveFormat::from_path(path).is_some(),
+    };
+
+    if !valid {
+        return Err(match (source_type, path.exists()) {
+            (SourceType::Git, _) => TemplateError::GitSourceUnreachable,
+            (_, false) => TemplateError::InvalidPath,
+            (_, true) => TemplateError::InvalidTemplate,
+        });
+    }
+
+    // Spawns can happen from any directory, local git repositories are kept absolute
+    let location = match (source_type, local) {
+        (SourceType::Git, true) => fs::canonicalize(path)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or(source),
+        _ => source,
+    };
+
+    Ok(Source { source_type, location })
+}
+
+/// Replaces a `prefix:rest` shorthand by its url. Sources without a known prefix are returned
+/// untouched.
+pub fn expand_shorthand(source: &str, shorthands: &HashMap<String, String>) -> String {
+    let (prefix, rest) = match source.split_once(':') {
+        Some((prefix, rest)) if !rest.starts_with("//") => (prefix, rest),
+        _ => return source.to_string(),
+    };
+
+    match shorthands.get(prefix) {
+        Some(url) if url.contains("{}") => url.replace("{}", rest),
+        Some(url) => format!("{}{}", url, rest),
+        None => source.to_string(),
+    }
+}
+
+/// Urls with a scheme (`https://`, `ssh://`, `file://`) or the scp-like `user@host:path`
+/// syntax are remote, anything else is a path in this machine.
+pub fn is_local_path(source: &str) -> bool {
+    if source.contains("://") {
+        return false;
+    }
+
+    match source.split_once(':') {
+        Some((host, _)) => host.contains('/') || host.len() == 1,
+        None => true,
+    }
+}
+
+fn is_bare_repository(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_shorthand() {
+        let shorthands = HashMap::from([
+            ("gh".to_string(), "https://github.com/{}.git".to_string()),
+            ("work".to_string(), "git@git.example.com:".to_string()),
+        ]);
+
+        assert_eq!(expand_shorthand("gh:org/repo", &shorthands), "https://github.com/org/repo.git");
+        assert_eq!(expand_shorthand("work:team/repo", &shorthands), "git@git.example.com:team/repo");
+        assert_eq!(expand_shorthand("https://host/org/repo", &shorthands), "https://host/org/repo");
+        assert_eq!(expand_shorthand("git@host:org/repo", &shorthands), "git@host:org/repo");
+    }
+
+    #[test]
+    fn test_remote_sources_are_git() {
+        for source in ["git@host:org/repo", "https://host/org/repo", "ssh://git@host/org/repo", "file:///srv/repo"] {
+            let resolved = resolve_with(source, None, &HashMap::new()).ok().map(|source| source.source_type);
+
+            assert_eq!(resolved, Some(SourceType::Git), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_is_local_path() {
+        assert!(is_local_path("/srv/templates/repo.git"));
+        assert!(is_local_path("../repo.git"));
+        assert!(is_local_path("C:/templates/repo.git"));
+        assert!(!is_local_path("https://github.com/ZocoLini/mk-template.git"));
+        assert!(!is_local_path("file:///srv/templates/repo.git"));
+        assert!(!is_local_path("git@github.com:ZocoLini/mk-template.git"));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::templates::TemplateError;
+use crate::{BIN_NAME, CONFIG_DIR};
+use serde_json::Value;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::{fs, io};
+
+/// Every template has its own directory in the templates' dir with its data and its payload:
+/// `<name>/template.json` and `<name>/payload/`, which is the directory of a dir template or
+/// holds the `template.txml` of a TXML template.
+pub const TEMPLATE_DATA_FILE: &str = "template.json";
+pub const PAYLOAD_DIR: &str = "payload";
+pub const TXML_PAYLOAD_FILE: &str = "template.txml";
+
+/// Locked while a process changes the store, so concurrent processes wait for each other.
+pub const LOCK_FILE: &str = "mkt.lock";
+
+/// Suffixes of the hidden directories used to swap templates. A new template is saved in
+/// `.<name>.new`, the previous version is kept in `.<name>.old` until the new one is in place
+/// and removed templates go through `.<name>.trash`. Bundles are unpacked in `.<id>.import`.
+const NEW_SUFFIX: &str = "new";
+const OLD_SUFFIX: &str = "old";
+const TRASH_SUFFIX: &str = "trash";
+const MIGRATING_SUFFIX: &str = "migrating";
+const IMPORT_SUFFIX: &str = "import";
+
+/// Depth of the nested locks of this process and the locked file.
+static LOCK: Mutex<(usize, Option<File>)> = Mutex::new((0, None));
+
+/// Held while the store is locked. Locks can be nested, the file is unlocked when the last
+/// one is dropped.
+pub struct StoreLock;
+
+pub fn lock() -> Result<StoreLock, TemplateError> {
+    let mut state = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    if state.0 == 0 {
+        let path = CONFIG_DIR.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|_e| TemplateError::IoError)?;
+
+        match file.try_lock() {
+            Ok(_) => {}
+            Err(TryLockError::WouldBlock) => {
+                eprintln!("Waiting for another {} process to release {}...", BIN_NAME, path.display());
+                file.lock().map_err(|_e| TemplateError::IoError)?;
+            }
+            Err(TryLockError::Error(_e)) => return Err(TemplateError::IoError),
+        }
+
+        state.1 = Some(file);
+    }
+
+    state.0 += 1;
+
+    Ok(StoreLock)
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let mut state = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        state.0 -= 1;
+
+        // Closing the file releases the lock
+        if state.0 == 0 {
+            state.1 = None;
+        }
+    }
+}
+
+/// Writes the file through a temporary file renamed over it, so it is never left half written.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.tmp", file_name));
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    fs::rename(&tmp, path)
+}
+
+pub fn template_dir(name: &str) -> PathBuf {
+    crate::templates::SAVE_TEMPLATES_DIR.join(name)
+}
+
+pub fn data_file(name: &str) -> PathBuf {
+    template_dir(name).join(TEMPLATE_DATA_FILE)
+}
+
+pub fn payload_dir(name: &str) -> PathBuf {
+    template_dir(name).join(PAYLOAD_DIR)
+}
+
+/// Name of the hidden directory a new version of the template is saved to before replacing
+/// the current one.
+pub fn new_name(name: &str) -> String {
+    format!(".{}.{}", name, NEW_SUFFIX)
+}
+
+/// Hidden directory a bundle is unpacked to before its templates are moved to the store.
+pub fn import_dir() -> PathBuf {
+    template_dir(&format!(".{}.{}", std::process::id(), IMPORT_SUFFIX))
+}
+
+/// Moves the template saved as `staging` to `name`. The current template, if any, is kept
+/// until the new one is in place.
+pub fn replace(staging: &str, name: &str) -> io::Result<()> {
+    let _lock = lock().map_err(|e| io::Error::other(format!("{:?}", e)))?;
+    let current = template_dir(name);
+    let backup = template_dir(&format!(".{}.{}", name, OLD_SUFFIX));
+
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+
+    if current.exists() {
+        fs::rename(&current, &backup)?;
+    }
+
+    if let Err(e) = fs::rename(template_dir(staging), &current) {
+        if backup.exists() {
+            fs::rename(&backup, &current)?;
+        }
+
+        return Err(e);
+    }
+
+    if backup.exists() {
+        fs::remove_dir_all(&backup)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the directory of a template. It is moved out of the way first, so an interrupted
+/// deletion never leaves a half deleted template behind.
+pub fn discard(name: &str) -> io::Result<()> {
+    let dir = template_dir(name);
+
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let trash = template_dir(&format!(".{}.{}", name.trim_start_matches('.'), TRASH_SUFFIX));
+
+    if trash.exists() {
+        fs::remove_dir_all(&trash)?;
+    }
+
+    fs::rename(&dir, &trash)?;
+    fs::remove_dir_all(&trash)
+}
+
+/// Deletes the payload of a template, a directory or a file. Its path comes from the template's
+/// data, so it is only deleted once it resolves to somewhere inside the store.
+pub fn remove_payload(path: &Path) -> io::Result<()> {
+    let store = fs::canonicalize(crate::templates::SAVE_TEMPLATES_DIR.as_path())?;
+    let payload = fs::canonicalize(path)?;
+
+    if payload == store || !payload.starts_with(&store) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is outside of {}", path.display(), store.display()),
+        ));
+    }
+
+    if payload.is_dir() {
+        fs::remove_dir_all(&payload)
+    } else {
+        fs::remove_file(&payload)
+    }
+}
+
+/// Finishes or rolls back what an interrupted process left in the store: previous versions
+/// are restored if their replacement never arrived, half saved templates and trash are
+/// deleted and migrated templates are moved to their directory.
+pub fn recover(store: &Path) {
+    let entries = match fs::read_dir(store) {
+        Ok(entries) => entries.flatten().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+
+    for entry in entries {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+
+        let (name, suffix) = match file_name.strip_prefix('.').and_then(|rest| rest.rsplit_once('.')) {
+            Some((name, suffix)) if entry.path().is_dir() => (name.to_string(), suffix.to_string()),
+            _ => continue,
+        };
+
+        let path = entry.path();
+        let target = store.join(&name);
+
+        let result = match suffix.as_str() {
+            OLD_SUFFIX | MIGRATING_SUFFIX if !target.exists() && path.join(TEMPLATE_DATA_FILE).is_file() => {
+                fs::rename(&path, &target)
+            }
+            // Migrations whose data file is still there are resumed by the migration
+            MIGRATING_SUFFIX => Ok(()),
+            OLD_SUFFIX | NEW_SUFFIX | TRASH_SUFFIX | IMPORT_SUFFIX => fs::remove_dir_all(&path),
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            eprintln!("{} couldn't be recovered: {e:?}", path.display());
+        }
+    }
+}
+
+/// Payload path of a dir template, relative to the templates' dir.
+pub fn dir_payload_path(name: &str) -> String {
+    format!("{}/{}", name, PAYLOAD_DIR)
+}
+
+/// Payload path of a TXML template, relative to the templates' dir.
+pub fn txml_payload_path(name: &str) -> String {
+    format!("{}/{}/{}", name, PAYLOAD_DIR, TXML_PAYLOAD_FILE)
+}
+
+/// Moves the templates saved by older versions, a `<name>` data file next to a `<name>.txml`
+/// or `<name>.dir` payload, to their own directory. Each template is staged in a hidden
+/// directory first, so an interrupted migration is resumed the next time without losing
+/// payloads.
+pub fn migrate_layout(store: &Path) {
+    let entries = match fs::read_dir(store) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut migrated = 0;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .filter(|data| data["class"].is_string() && data["data_path"].is_string());
+
+        let mut data = match data {
+            Some(data) => data,
+            None => continue,
+        };
+
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        match migrate_template(store, &name, &mut data) {
+            Ok(_) => migrated += 1,
+            Err(e) => eprintln!("The template {} couldn't be moved to the new layout: {e:?}", name),
+        }
+    }
+
+    if migrated > 0 {
+        eprintln!("Moved {} template(s) to the new layout of {}.", migrated, store.display());
+    }
+}
+
+fn migrate_template(store: &Path, name: &str, data: &mut Value) -> io::Result<()> {
+    let staging = store.join(format!(".{}.{}", name, MIGRATING_SUFFIX));
+    let staged_payload = staging.join(PAYLOAD_DIR);
+    let staged_txml = staged_payload.join(TXML_PAYLOAD_FILE);
+
+    fs::create_dir_all(&staging)?;
+
+    let data_path = data["data_path"].as_str().unwrap_or_default();
+    let old_payload = match Path::new(data_path).is_absolute() {
+        true => PathBuf::from(data_path),
+        false => store.join(data_path),
+    };
+
+    // Only the payloads saved for this template are moved, any other is left where it is
+    let owned = [format!("{}.txml", name), format!("{}.dir", name)];
+    let in_store = data["class"] != "git"
+        && old_payload.parent() == Some(store)
+        && old_payload.file_name().is_some_and(|file_name| owned.iter().any(|owned| file_name == owned.as_str()));
+
+    if in_store && old_payload.is_dir() {
+        if staged_payload.exists() {
+            fs::remove_dir_all(&staged_payload)?;
+        }
+
+        fs::rename(&old_payload, &staged_payload)?;
+    } else if in_store && old_payload.is_file() {
+        fs::create_dir_all(&staged_payload)?;
+        fs::rename(&old_payload, &staged_txml)?;
+    }
+
+    if staged_txml.is_file() {
+        data["data_path"] = Value::String(txml_payload_path(name));
+    } else if staged_payload.is_dir() {
+        data["data_path"] = Value::String(dir_payload_path(name));
+    }
+
+    let content = serde_json::to_string_pretty(data).map_err(io::Error::other)?;
+
+    fs::write(staging.join(TEMPLATE_DATA_FILE), content)?;
+    fs::remove_file(store.join(name))?;
+    fs::rename(&staging, store.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_layout() {
+        let store = std::env::temp_dir().join(format!("mkt-store-test-{}", std::process::id()));
+        fs::create_dir_all(store.join("dir_template.dir")).unwrap();
+        fs::write(store.join("dir_template.dir").join("file"), "content").unwrap();
+        fs::write(store.join("txml_template.txml"), "<Root/>").unwrap();
+        fs::write(store.join("dir_template"), r#"{"class": "dir", "data_path": "dir_template.dir"}"#).unwrap();
+        fs::write(
+            store.join("txml_template"),
+            format!(r#"{{"class": "txml", "data_path": "{}"}}"#, store.join("txml_template.txml").display()),
+        )
+        .unwrap();
+        fs::write(store.join("git_template"), r#"{"class": "git", "data_path": "https://host/repo.git"}"#).unwrap();
+
+        migrate_layout(&store);
+
+        let data = |name: &str| {
+            let content = fs::read_to_string(store.join(name).join(TEMPLATE_DATA_FILE)).unwrap();
+            serde_json::from_str::<Value>(&content).unwrap()["data_path"].as_str().unwrap().to_string()
+        };
+
+        assert_eq!(data("dir_template"), "dir_template/payload");
+        assert_eq!(data("txml_template"), "txml_template/payload/template.txml");
+        assert_eq!(data("git_template"), "https://host/repo.git");
+        assert!(store.join("dir_template/payload/file").is_file());
+        assert!(store.join("txml_template/payload/template.txml").is_file());
+        assert!(!store.join("dir_template.dir").exists());
+
+        fs::remove_dir_all(store).unwrap();
+    }
+}
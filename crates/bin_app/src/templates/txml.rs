@@ -1,8 +1,9 @@
 use crate::templates::data::TemplateData;
-use crate::templates::{Template, TemplateError, SAVE_TEMPLATES_DIR};
+use crate::templates::{search, store, variable_values, PendingStep, Template, TemplateError};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
+use txml_processor::txml_elements::TemplateMetadata;
 use txml_processor::txml_structure::TxmlStructure;
 use txml_processor::Instantiable;
 
@@ -16,17 +17,25 @@ impl TxmlTemplate {
     pub fn new(txml_file: PathBuf) -> Self {
         Self { txml_file }
     }
+
+    fn parse(&self, flags: &HashMap<String, Vec<String>>) -> Result<TxmlStructure, TemplateError> {
+        let values = variable_values(flags)?;
+
+        TxmlStructure::from_txml_file_with_values(&self.txml_file, &values).map_err(|e| {
+            eprintln!("Error processing txml: {:?}", e);
+            TemplateError::InvalidTemplate
+        })
+    }
 }
 
 impl Template for TxmlTemplate {
-    fn generate(&self, name: &str, _flags: HashMap<String, String>) -> Result<(), TemplateError> {
-        let txml_structure = match TxmlStructure::from_txml_file(&self.txml_file) {
-            Ok(txml) => txml,
-            Err(e) => {
-                println!("Error processing txml: {:?}", e);
-                return Err(TemplateError::InvalidTemplate);
-            }
-        };
+    fn generate(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
+        let mut txml_structure = self.parse(&flags)?;
+
+        // Spawns into an archive only render files unless the commands are allowed
+        if flags.contains_key("--archive") && !flags.contains_key("--allow-commands") {
+            txml_structure.strip_commands();
+        }
 
         txml_structure.instantiate_with_name(
             &env::current_dir().expect("Should exist a current dir."),
@@ -36,29 +45,43 @@ impl Template for TxmlTemplate {
         Ok(())
     }
 
-    fn save(&self, name: &str, _flags: HashMap<String, String>) -> Result<(), TemplateError> {
+    fn generate_staged(&self, name: &str, flags: HashMap<String, Vec<String>>) -> Result<Vec<PendingStep>, TemplateError> {
+        let mut txml_structure = self.parse(&flags)?;
+        let commands = txml_structure.take_commands(name);
+
+        txml_structure.instantiate_with_name(
+            &env::current_dir().expect("Should exist a current dir."),
+            name,
+        );
+
+        Ok(commands.into_iter().map(PendingStep::Command).collect())
+    }
+
+    fn save(&self, name: &str, _flags: HashMap<String, Vec<String>>) -> Result<(), TemplateError> {
         let src = self.txml_file.as_path();
-        let dst = SAVE_TEMPLATES_DIR
-            .as_path()
-            .join(name.to_string() + ".txml");
+        let dst = store::payload_dir(name).join(store::TXML_PAYLOAD_FILE);
         let dst = dst.as_path();
 
-        let result = fs::copy(src, dst).map_err(|_e| TemplateError::IoError);
+        fs::create_dir_all(store::payload_dir(name)).map_err(|_e| TemplateError::IoError)?;
+        fs::copy(src, dst).map_err(|_e| TemplateError::IoError)?;
 
-        if let Err(e) = result {
-            return Err(e);
+        let mut template_data = TemplateData::new(TXML_TEMPLATE, &store::txml_payload_path(name));
+
+        // Tags that aren't valid are left out, they can still be fixed with the tag command
+        if let Some(metadata) = read_metadata(src) {
+            template_data.set_tags(metadata.tags.iter().filter_map(|tag| search::normalize_tag(tag).ok()).collect());
         }
 
-        TemplateData::new(TXML_TEMPLATE, dst.to_str().expect("Should be an String"))
-            .save(name)
-            .map_err(|_e| {
-                fs::remove_file(dst).expect("Should remove the file.");
-                TemplateError::IoError
-            })
+        template_data.save(name).map_err(|_e| TemplateError::IoError)
     }
 
     fn remove(&self) {
-        if let Err(e) = fs::remove_file(&self.txml_file) {
+        // The payload lives in the template's directory, it is only left by older versions
+        if !self.txml_file.exists() {
+            return;
+        }
+
+        if let Err(e) = store::remove_payload(&self.txml_file) {
             println!("The TXML Template couldn't be deleted: {e:?}")
         }
     }
@@ -68,11 +91,19 @@ impl Template for TxmlTemplate {
     }
 
     fn get_description(&self) -> String {
-        let txml_structure =
-            TxmlStructure::from_txml_file(&self.txml_file).expect("Should be a valid TXML file.");
-
-        let description = txml_structure.metadata().description.clone();
+        // Only the Metadata is read, parsing the whole TXML would ask for its variables
+        let description = match read_metadata(&self.txml_file) {
+            Some(metadata) => metadata.description,
+            None => return "Invalid TXML Template".to_string(),
+        };
 
         if description.is_empty() { "TXML Template".to_string() } else { description }
     }
 }
+
+/// Metadata of a TXML file, read without resolving its variables.
+pub fn read_metadata(txml_file: &Path) -> Option<TemplateMetadata> {
+    let txml = fs::read_to_string(txml_file).ok()?;
+
+    TxmlStructure::obtain_metadata(&txml).ok()
+}
@@ -1,21 +1,20 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::io;
 use std::process::Command;
 use std::fmt::{Debug, Formatter};
 
-pub fn execute_commands(command: &str, dir: &PathBuf) -> Result<(), CommandError>
+pub fn execute_commands(command: &str, dir: &Path) -> Result<(), CommandError>
 {
     let commands: Vec<&str> = command.split(";").collect();
 
     for &command in commands.iter() {
-        if let Err(e) = execute_command(command.trim(), dir) {
-            return Err(e);
-        }
+        execute_command(command.trim(), dir)?
     }
     
     Ok(())
 }
 
-fn execute_command(command: &str, dir: &PathBuf) -> Result<(), CommandError>
+fn execute_command(command: &str, dir: &Path) -> Result<(), CommandError>
 {
     let command_parts: Vec<&str> = command.split_whitespace().collect();
 
@@ -26,7 +25,8 @@ fn execute_command(command: &str, dir: &PathBuf) -> Result<(), CommandError>
     let cmd = command_parts[0];
     let args = &command_parts[1..];
 
-    let status = Command::new(cmd).current_dir(dir).args(args).status();
+    // The output goes to the stderr, the stdout may be carrying the spawned files
+    let status = Command::new(cmd).current_dir(dir).args(args).stdout(io::stderr()).status();
 
     let status = match status {
         Ok(s) => s,
@@ -40,6 +40,46 @@ fn execute_command(command: &str, dir: &PathBuf) -> Result<(), CommandError>
     }
 }
 
+/// A command of a template taken out of it to be run later, e.g. once the spawned files are
+/// confirmed.
+pub struct DeferredCommand
+{
+    entry: PathBuf,
+    dir: PathBuf,
+    command: String,
+}
+
+impl DeferredCommand
+{
+    /// `element` is the path of the file or directory the command belongs to and `dir` the one
+    /// it runs in, both relative to the directory the template is spawned in.
+    pub(crate) fn new(element: &Path, dir: &Path, command: String) -> DeferredCommand
+    {
+        DeferredCommand {
+            entry: element.components().next().map(|component| PathBuf::from(component.as_os_str())).unwrap_or_default(),
+            dir: dir.to_path_buf(),
+            command,
+        }
+    }
+
+    /// Top-level entry of the spawn the command belongs to.
+    pub fn entry(&self) -> &Path
+    {
+        &self.entry
+    }
+
+    pub fn command(&self) -> &str
+    {
+        &self.command
+    }
+
+    /// Runs the command where the spawn would have, with `root` as the spawn directory.
+    pub fn run(&self, root: &Path) -> Result<(), CommandError>
+    {
+        execute_commands(&self.command, &root.join(&self.dir))
+    }
+}
+
 pub enum CommandError
 {
     CommandFailed,
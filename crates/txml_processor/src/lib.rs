@@ -1,10 +1,11 @@
 use quick_xml::events::attributes::Attribute;
-use std::path::PathBuf;
+use std::path::Path;
 use std::io;
 
 pub mod txml_elements;
 pub mod txml_structure;
-mod commands;
+pub mod variables;
+pub mod commands;
 mod reader;
 
 pub trait AttributeHandler {
@@ -12,13 +13,13 @@ pub trait AttributeHandler {
 }
 
 pub trait Instantiable {
-    fn instantiate(&self, dir: &PathBuf);
+    fn instantiate(&self, dir: &Path);
 
-    fn instantiate_with_name(&self, dir: &PathBuf, _name: &str);
+    fn instantiate_with_name(&self, dir: &Path, _name: &str);
 }
 
 pub trait FsElement {
-    fn from_path(path: &PathBuf) -> Result<Self, io::Error> where Self: Sized;
+    fn from_path(path: &Path) -> Result<Self, io::Error> where Self: Sized;
 }
 
 pub trait TxmlElement {
@@ -12,6 +12,7 @@ pub enum TxmlEvent<'a> {
     Variable(ElementState<'a>),
     Directory(ElementState<'a>),
     File(ElementState<'a>),
+    Symlink(ElementState<'a>),
     Text(BytesText<'a>),
     Comment(()),
     Declaration(()),
@@ -30,7 +31,7 @@ pub struct TxmlReader<'a> {
 }
 
 impl<'a> TxmlReader<'a> {
-    pub fn read_event(&mut self) -> Result<TxmlEvent, TxmlReaderError> {
+    pub fn read_event(&mut self) -> Result<TxmlEvent<'_>, TxmlReaderError> {
         self.event_buff.clear();
 
         match self.xml_reader.read_event_into(&mut self.event_buff) {
@@ -40,6 +41,7 @@ impl<'a> TxmlReader<'a> {
                 b"Variable" => Ok(TxmlEvent::Variable(ElementState::Start(a))),
                 b"Directory" => Ok(TxmlEvent::Directory(ElementState::Start(a))),
                 b"File" => Ok(TxmlEvent::File(ElementState::Start(a))),
+                b"Symlink" => Ok(TxmlEvent::Symlink(ElementState::Start(a))),
                 _ => Err(TxmlReaderError::UnexpectedElement),
             },
             Ok(Event::Empty(a)) => match a.name().0 {
@@ -48,6 +50,7 @@ impl<'a> TxmlReader<'a> {
                 b"Variable" => Ok(TxmlEvent::Variable(ElementState::Empty(a))),
                 b"Directory" => Ok(TxmlEvent::Directory(ElementState::Empty(a))),
                 b"File" => Ok(TxmlEvent::File(ElementState::Empty(a))),
+                b"Symlink" => Ok(TxmlEvent::Symlink(ElementState::Empty(a))),
                 _ => Err(TxmlReaderError::UnexpectedElement),
             }
             Ok(Event::Text(a)) => Ok(TxmlEvent::Text(a)),
@@ -57,6 +60,7 @@ impl<'a> TxmlReader<'a> {
                 b"Variable" => Ok(TxmlEvent::Variable(ElementState::End)),
                 b"Directory" => Ok(TxmlEvent::Directory(ElementState::End)),
                 b"File" => Ok(TxmlEvent::File(ElementState::End)),
+                b"Symlink" => Ok(TxmlEvent::Symlink(ElementState::End)),
                 _ => Err(TxmlReaderError::UnexpectedElement),
             },
             Ok(Event::Comment(_a)) => Ok(TxmlEvent::Comment(())),
@@ -1,8 +1,10 @@
+use crate::variables::replace_literals;
+use crate::commands::DeferredCommand;
 use crate::{commands, AttributeHandler, FsElement, Instantiable, TxmlElement};
 use quick_xml::events::attributes::Attribute;
 use std::io::Write;
-use std::path::PathBuf;
-use std::{fs, io};
+use std::path::Path;
+use std::{fs, io, mem};
 
 // region: Directory
 
@@ -10,8 +12,16 @@ pub struct Directory {
     name: String,
     out_command: String,
     in_command: String,
+    mode: String,
     files: Vec<File>,
     directories: Vec<Directory>,
+    symlinks: Vec<Symlink>,
+}
+
+impl Default for Directory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Directory {
@@ -20,8 +30,10 @@ impl Directory {
             name: String::new(),
             out_command: String::new(),
             in_command: String::new(),
+            mode: String::new(),
             files: Vec::new(),
             directories: Vec::new(),
+            symlinks: Vec::new(),
         }
     }
 
@@ -32,11 +44,86 @@ impl Directory {
     pub fn add_directory(&mut self, directory: Directory) {
         self.directories.push(directory);
     }
+
+    pub fn add_symlink(&mut self, symlink: Symlink) {
+        self.symlinks.push(symlink);
+    }
+
+    /// Replaces the literals of the pairs in the names and contents of everything inside.
+    pub fn templatize(&mut self, pairs: &[(String, String)]) {
+        self.name = replace_literals(&self.name, pairs);
+
+        self.files.iter_mut().for_each(|file| file.templatize(pairs));
+        self.directories.iter_mut().for_each(|directory| directory.templatize(pairs));
+        self.symlinks.iter_mut().for_each(|symlink| symlink.templatize(pairs));
+    }
+
+    /// Drops the commands of this directory and of everything inside, so it is only created.
+    pub fn strip_commands(&mut self) {
+        self.in_command.clear();
+        self.out_command.clear();
+
+        self.files.iter_mut().for_each(File::strip_commands);
+        self.directories.iter_mut().for_each(Directory::strip_commands);
+    }
+
+    /// Same as `strip_commands` but keeping the commands, in the order they would run, when
+    /// instantiated in `dir` with the name, or its own one.
+    pub fn take_commands(&mut self, dir: &Path, name: Option<&str>, commands: &mut Vec<DeferredCommand>) {
+        let path = dir.join(name.unwrap_or(&self.name));
+
+        if !self.in_command.is_empty() {
+            commands.push(DeferredCommand::new(&path, &path, mem::take(&mut self.in_command)));
+        }
+
+        if !self.out_command.is_empty() {
+            commands.push(DeferredCommand::new(&path, dir, mem::take(&mut self.out_command)));
+        }
+
+        self.files.iter_mut().for_each(|file| file.take_commands(&path, None, commands));
+        self.directories.iter_mut().for_each(|directory| directory.take_commands(&path, None, commands));
+    }
+
+    /// Same as `from_path` but skipping every entry, at any depth, for which `filter` returns false.
+    pub fn from_path_filtered(dir: &Path, filter: &dyn Fn(&Path) -> bool) -> Result<Directory, io::Error> {
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Directory not found",
+            ));
+        }
+
+        let mut dir_element = Directory {
+            name: dir.file_name().expect("Should have a name").to_str().unwrap().to_string(),
+            out_command: String::from(""),
+            in_command: String::from(""),
+            mode: read_mode(dir)?,
+            files: Vec::new(),
+            directories: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        for entry in dir.read_dir()? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+
+            if !filter(&path) {
+                continue;
+            }
+
+            if file_type.is_symlink() { dir_element.symlinks.push(Symlink::from_path(&path)?) }
+            else if file_type.is_file() { dir_element.files.push(File::from_path(&path)?) }
+            else { dir_element.directories.push(Directory::from_path_filtered(&path, filter)?) }
+        }
+
+        Ok(dir_element)
+    }
 }
 
 impl TxmlElement for Directory {
     fn into_txml_element(self) -> String {
-        let mut result = format!("<Directory name=\"{}\"", self.name);
+        let mut result = format!("<Directory name=\"{}\"", reverse_escape_xml(&self.name));
         
         if !self.in_command.is_empty() {
             result.push_str(&format!(" in_command=\"{}\"", self.in_command));
@@ -46,6 +133,10 @@ impl TxmlElement for Directory {
             result.push_str(&format!(" out_command=\"{}\"", self.out_command));
         }
         
+        if !self.mode.is_empty() {
+            result.push_str(&format!(" mode=\"{}\"", self.mode));
+        }
+        
         result.push_str(">\n");
         
         for file in self.files {
@@ -56,6 +147,10 @@ impl TxmlElement for Directory {
             result.push_str(&directory.into_txml_element());
         }
         
+        for symlink in self.symlinks {
+            result.push_str(&symlink.into_txml_element());
+        }
+        
         result.push_str("</Directory>\n");
         
         result
@@ -63,16 +158,16 @@ impl TxmlElement for Directory {
 }
 
 impl Instantiable for Directory {
-    fn instantiate(&self, dir: &PathBuf) {
+    fn instantiate(&self, dir: &Path) {
         self.instantiate_with_name(dir, self.name.as_str());
     }
 
-    fn instantiate_with_name(&self, dir: &PathBuf, name: &str) {
-        let dir_name = format!("{}", name);
+    fn instantiate_with_name(&self, dir: &Path, name: &str) {
+        let dir_name = name.to_string();
         let new_path_buff = dir.join(&dir_name);
 
         if new_path_buff.exists() {
-            println!("Directory {} already exist. Skipping creation.", dir_name);
+            eprintln!("Directory {} already exist. Skipping creation.", dir_name);
             return;
         }
 
@@ -83,18 +178,16 @@ impl Instantiable for Directory {
         if !self.in_command.is_empty() {
             let command_execution = commands::execute_commands(&self.in_command, &new_path_buff);
 
-            match command_execution {
-                Err(e) => println!("File {} created but the command failed: {e:?}", dir_name),
-                _ => (),
+            if let Err(e) = command_execution {
+                eprintln!("File {} created but the command failed: {e:?}", dir_name);
             }
         }
 
         if !self.out_command.is_empty() {
             let command_execution = commands::execute_commands(&self.out_command, dir);
 
-            match command_execution {
-                Err(e) => println!("File {} created but the command failed: {e:?}", dir_name),
-                _ => (),
+            if let Err(e) = command_execution {
+                eprintln!("File {} created but the command failed: {e:?}", dir_name);
             }
         }
 
@@ -105,6 +198,13 @@ impl Instantiable for Directory {
         self.directories
             .iter()
             .for_each(|directory| directory.instantiate(&new_path_buff));
+
+        self.symlinks
+            .iter()
+            .for_each(|symlink| symlink.instantiate(&new_path_buff));
+
+        // Applied last so a read-only directory doesn't prevent creating its content
+        apply_mode(&new_path_buff, &self.mode);
     }
 }
 
@@ -112,7 +212,7 @@ impl AttributeHandler for Directory {
     fn process_attribute(&mut self, attribute: Attribute) {
         match attribute.key.0 {
             b"name" => {
-                self.name = String::from_utf8_lossy(&attribute.value).to_string();
+                self.name = escape_xml(&String::from_utf8_lossy(&attribute.value));
             }
             b"in_command" => {
                 self.in_command = String::from_utf8_lossy(&attribute.value).to_string()
@@ -120,7 +220,10 @@ impl AttributeHandler for Directory {
             b"out_command" => {
                 self.out_command = String::from_utf8_lossy(&attribute.value).to_string()
             }
-            _ => println!(
+            b"mode" => {
+                self.mode = String::from_utf8_lossy(&attribute.value).to_string()
+            }
+            _ => eprintln!(
                 "Unknown attribute for Directory: {}",
                 String::from_utf8_lossy(attribute.key.0)
             ),
@@ -129,31 +232,8 @@ impl AttributeHandler for Directory {
 }
 
 impl FsElement for Directory {
-    fn from_path(dir: &PathBuf) -> Result<Directory, io::Error> {
-        if !dir.is_dir() {
-            return Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Directory not found",
-            ));
-        }
-
-        let mut dir_element = Directory {
-            name: dir.file_name().expect("Should have a name").to_str().unwrap().to_string(),
-            out_command: String::from(""),
-            in_command: String::from(""),
-            files: Vec::new(),
-            directories: Vec::new(),
-        };
-
-        for entry in dir.read_dir()? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_file() { dir_element.files.push(File::from_path(&path)?) }
-            else { dir_element.directories.push(Directory::from_path(&path)?) }
-        }
-
-        Ok(dir_element)
+    fn from_path(dir: &Path) -> Result<Directory, io::Error> {
+        Directory::from_path_filtered(dir, &|_| true)
     }
 }
 
@@ -165,15 +245,23 @@ pub struct File {
     name: String,
     extension: String,
     command: String,
+    mode: String,
     content: String,
 }
 
+impl Default for File {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl File {
     pub fn new() -> File {
         File {
             name: String::new(),
             extension: String::new(),
             command: String::new(),
+            mode: String::new(),
             content: String::new(),
         }
     }
@@ -181,25 +269,56 @@ impl File {
     pub fn set_text(&mut self, text: String) {
         self.content = text;
     }
+
+    /// Replaces the literals of the pairs in the name, extension and content.
+    pub fn templatize(&mut self, pairs: &[(String, String)]) {
+        self.name = replace_literals(&self.name, pairs);
+        self.extension = replace_literals(&self.extension, pairs);
+        self.content = replace_literals(&self.content, pairs);
+    }
+
+    pub fn strip_commands(&mut self) {
+        self.command.clear();
+    }
+
+    /// Same as `strip_commands` but keeping the command, to run it later in `dir`.
+    pub fn take_commands(&mut self, dir: &Path, name: Option<&str>, commands: &mut Vec<DeferredCommand>) {
+        if !self.command.is_empty() {
+            let path = dir.join(self.file_name(name.unwrap_or(&self.name)));
+            commands.push(DeferredCommand::new(&path, dir, mem::take(&mut self.command)));
+        }
+    }
+
+    fn file_name(&self, name: &str) -> String {
+        if self.extension.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", name, self.extension)
+        }
+    }
 }
 
 impl TxmlElement for File {
     fn into_txml_element(self) -> String {
-        let mut result = format!("<File name=\"{}\"", self.name);
+        let mut result = format!("<File name=\"{}\"", reverse_escape_xml(&self.name));
         
         if !self.extension.is_empty() { 
-            result.push_str(&format!(" extension=\"{}\"", self.extension));
+            result.push_str(&format!(" extension=\"{}\"", reverse_escape_xml(&self.extension)));
         }
         
         if !self.command.is_empty() {
             result.push_str(&format!(" command=\"{}\"", self.command));
         }
         
+        if !self.mode.is_empty() {
+            result.push_str(&format!(" mode=\"{}\"", self.mode));
+        }
+        
         result.push_str(">\n");
         
         result.push_str(&reverse_escape_xml(&self.content));
         
-        result.push_str("\n");
+        result.push('\n');
         
         result.push_str("</File>\n");
         
@@ -208,20 +327,16 @@ impl TxmlElement for File {
 }
 
 impl Instantiable for File {
-    fn instantiate(&self, dir: &PathBuf) {
+    fn instantiate(&self, dir: &Path) {
         self.instantiate_with_name(dir, self.name.as_str());
     }
 
-    fn instantiate_with_name(&self, dir: &PathBuf, name: &str) {
-        let file_name = if self.extension.is_empty() {
-            format!("{}", name)
-        } else {
-            format!("{}.{}", name, self.extension)
-        };
+    fn instantiate_with_name(&self, dir: &Path, name: &str) {
+        let file_name = self.file_name(name);
         let new_path_buff = dir.join(&file_name);
 
         if new_path_buff.exists() {
-            println!("File {} already exist. Skipping creation.", file_name);
+            eprintln!("File {} already exist. Skipping creation.", file_name);
             return;
         }
 
@@ -233,19 +348,20 @@ impl Instantiable for File {
             .write_all(content.as_bytes())
             .expect("Error writing to file");
 
+        apply_mode(&new_path_buff, &self.mode);
+
         if !self.command.is_empty() {
             let command_execution = commands::execute_commands(&self.command, dir);
 
-            match command_execution {
-                Err(e) => println!("File {} created but the command failed: {e:?}", file_name),
-                _ => (),
+            if let Err(e) = command_execution {
+                eprintln!("File {} created but the command failed: {e:?}", file_name);
             }
         }
     }
 }
 
 impl FsElement for File {
-    fn from_path(path: &PathBuf) -> Result<Self, io::Error>
+    fn from_path(path: &Path) -> Result<Self, io::Error>
     {
         if !path.is_file() {
             return Err(io::Error::new(
@@ -261,7 +377,8 @@ impl FsElement for File {
                            .unwrap_or("")
                            .to_string(),
             command: String::from(""),
-            content: fs::read_to_string(path).map_err(|_| io::Error::new(io::ErrorKind::Other, "Error reading file"))?,
+            mode: read_mode(path)?,
+            content: fs::read_to_string(path).map_err(|_| io::Error::other("Error reading file"))?,
         };
 
         Ok(file_element)
@@ -272,15 +389,18 @@ impl AttributeHandler for File {
     fn process_attribute(&mut self, attribute: Attribute) {
         match attribute.key.0 {
             b"name" => {
-                self.name = String::from_utf8_lossy(&attribute.value).to_string();
+                self.name = escape_xml(&String::from_utf8_lossy(&attribute.value));
             }
             b"extension" => {
-                self.extension = String::from_utf8_lossy(&attribute.value).to_string();
+                self.extension = escape_xml(&String::from_utf8_lossy(&attribute.value));
             }
             b"command" => {
                 self.command = String::from_utf8_lossy(&attribute.value).to_string();
             }
-            _ => println!(
+            b"mode" => {
+                self.mode = String::from_utf8_lossy(&attribute.value).to_string();
+            }
+            _ => eprintln!(
                 "Unknown attribute for File: {}",
                 String::from_utf8_lossy(attribute.key.0)
             ),
@@ -289,11 +409,12 @@ impl AttributeHandler for File {
 }
 
 fn escape_xml(text: &str) -> String {
-    text.replace("&amp;", "&")
-        .replace("&lt;", "<")
+    // &amp; goes last, so an escaped entity, e.g. `&amp;lt;`, is only unescaped once
+    text.replace("&lt;", "<")
         .replace("&gt;", ">")
         .replace("&quot;", "\"")
         .replace("&apos;", "'")
+        .replace("&amp;", "&")
 }
 
 fn reverse_escape_xml(text: &str) -> String {
@@ -322,12 +443,12 @@ fn remove_indentation(text: &str) -> String {
     // Removing the spaces from the beginning of each line
     for line in text.lines().skip(1) {
         if is_blank(line) {
-            result.push_str("\n");
+            result.push('\n');
             continue;
         }
 
         result.push_str(&line[indentation..]);
-        result.push_str("\n");
+        result.push('\n');
     }
 
     result.pop();
@@ -353,7 +474,7 @@ fn get_indentation(text_lines: &Vec<&str>) -> usize {
             continue;
         }
 
-        let line_indentation = count_spaces(*line);
+        let line_indentation = count_spaces(line);
         if line_indentation < indentation {
             indentation = line_indentation;
         }
@@ -367,6 +488,152 @@ fn is_blank(text: &str) -> bool {
 
 // endregion: File
 
+// region: Symlink
+
+pub struct Symlink {
+    name: String,
+    target: String,
+}
+
+impl Default for Symlink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Symlink {
+    pub fn new() -> Symlink {
+        Symlink {
+            name: String::new(),
+            target: String::new(),
+        }
+    }
+
+    /// Replaces the literals of the pairs in the name and target.
+    pub fn templatize(&mut self, pairs: &[(String, String)]) {
+        self.name = replace_literals(&self.name, pairs);
+        self.target = replace_literals(&self.target, pairs);
+    }
+}
+
+impl TxmlElement for Symlink {
+    fn into_txml_element(self) -> String {
+        format!(
+            "<Symlink name=\"{}\" target=\"{}\"/>\n",
+            reverse_escape_xml(&self.name),
+            reverse_escape_xml(&self.target)
+        )
+    }
+}
+
+impl Instantiable for Symlink {
+    fn instantiate(&self, dir: &Path) {
+        self.instantiate_with_name(dir, self.name.as_str());
+    }
+
+    fn instantiate_with_name(&self, dir: &Path, name: &str) {
+        let new_path_buff = dir.join(name);
+
+        if new_path_buff.symlink_metadata().is_ok() {
+            eprintln!("Symlink {} already exist. Skipping creation.", name);
+            return;
+        }
+
+        #[cfg(unix)]
+        if let Err(e) = std::os::unix::fs::symlink(escape_xml(&self.target), &new_path_buff) {
+            eprintln!("Symlink {} couldn't be created: {e:?}", name);
+        }
+
+        #[cfg(not(unix))]
+        eprintln!("Symlinks are not supported on this platform. Skipping {}.", name);
+    }
+}
+
+impl FsElement for Symlink {
+    fn from_path(path: &Path) -> Result<Self, io::Error>
+    {
+        let target = fs::read_link(path)?;
+
+        let symlink_element = Symlink {
+            name: path.file_name().expect("Should have a name").to_str().unwrap().to_string(),
+            target: target
+                .to_str()
+                .ok_or_else(|| io::Error::other("Symlink target is not valid UTF-8"))?
+                .to_string(),
+        };
+
+        Ok(symlink_element)
+    }
+}
+
+impl AttributeHandler for Symlink {
+    fn process_attribute(&mut self, attribute: Attribute) {
+        match attribute.key.0 {
+            b"name" => {
+                self.name = escape_xml(&String::from_utf8_lossy(&attribute.value));
+            }
+            b"target" => {
+                self.target = String::from_utf8_lossy(&attribute.value).to_string();
+            }
+            _ => eprintln!(
+                "Unknown attribute for Symlink: {}",
+                String::from_utf8_lossy(attribute.key.0)
+            ),
+        }
+    }
+}
+
+// endregion: Symlink
+
+// region: Permissions
+
+/// Returns the permission bits of the path as an octal string, e.g. "755".
+/// Always empty on platforms without unix permissions.
+fn read_mode(path: &Path) -> Result<String, io::Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path)?.permissions().mode() & 0o7777;
+        Ok(format!("{:o}", mode))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(String::new())
+    }
+}
+
+/// Sets the permission bits described by an octal string on the path. Empty modes are ignored.
+fn apply_mode(path: &Path, mode: &str) {
+    if mode.is_empty() {
+        return;
+    }
+
+    let mode = match u32::from_str_radix(mode, 8) {
+        Ok(mode) if mode <= 0o7777 => mode,
+        _ => {
+            eprintln!("Invalid mode '{}' for {}. Keeping the default permissions.", mode, path.display());
+            return;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+            eprintln!("Couldn't set the mode of {}: {e:?}", path.display());
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = mode;
+}
+
+// endregion: Permissions
+
 // region: Variable
 
 pub struct Variable {
@@ -374,6 +641,12 @@ pub struct Variable {
     value: String,
 }
 
+impl Default for Variable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Variable {
     pub fn new() -> Variable {
         Variable {
@@ -381,6 +654,31 @@ impl Variable {
             value: String::new(),
         }
     }
+
+    /// A variable without value, asked when the template is spawned.
+    pub fn named(name: &str) -> Variable {
+        Self::with_value(name, "")
+    }
+
+    pub fn with_value(name: &str, value: &str) -> Variable {
+        Variable {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    /// Returns the value of the variable, asking for it in the stdin if it doesn't have one.
+    pub fn resolve_value(&self) -> String {
+        if !self.value.is_empty() {
+            return self.value.clone();
+        }
+
+        // Asked in the stderr, the stdout may be carrying the spawned files
+        eprintln!("Please, introduce the value for the variable '{}'", self.name);
+        let mut value = String::new();
+        io::stdin().read_line(&mut value).expect("Error reading from stdin");
+        value.trim().to_string()
+    }
     
     pub fn get_name(&self) -> &str {
         self.name.as_str()
@@ -400,7 +698,7 @@ impl AttributeHandler for Variable {
             b"value" => {
                 self.value = String::from_utf8_lossy(&attribute.value).to_string()
             }
-            _ => println!(
+            _ => eprintln!(
                 "Unknown attribute for Variable: {}",
                 String::from_utf8_lossy(attribute.key.0)
             ),
@@ -408,6 +706,16 @@ impl AttributeHandler for Variable {
     }
 }
 
+impl TxmlElement for Variable {
+    fn into_txml_element(self) -> String {
+        if self.value.is_empty() {
+            format!("<Variable name=\"{}\"/>\n", self.name)
+        } else {
+            format!("<Variable name=\"{}\" value=\"{}\"/>\n", self.name, self.value)
+        }
+    }
+}
+
 // endregion: Variable
 
 // region: TxmlMetadata
@@ -417,6 +725,14 @@ pub struct TemplateMetadata {
     pub date: String,
     pub version: String,
     pub description: String,
+    /// Comma separated in the `tags` attribute, e.g. `tags="rust, cli"`.
+    pub tags: Vec<String>,
+}
+
+impl Default for TemplateMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TemplateMetadata {
@@ -426,6 +742,7 @@ impl TemplateMetadata {
             date: String::new(),
             version: String::new(),
             description: String::new(),
+            tags: Vec::new(),
         }
     }
 }
@@ -436,7 +753,14 @@ impl AttributeHandler for TemplateMetadata {
             b"author" => self.author = String::from_utf8_lossy(&attribute.value).to_string(),
             b"date" => self.date = String::from_utf8_lossy(&attribute.value).to_string(),
             b"version" => self.version = String::from_utf8_lossy(&attribute.value).to_string(),
-            b"description" => self.description = String::from_utf8_lossy(&attribute.value).to_string(),
+            b"description" => self.description = escape_xml(&String::from_utf8_lossy(&attribute.value)),
+            b"tags" => {
+                self.tags = String::from_utf8_lossy(&attribute.value)
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            }
             _ => (),
         }
     }
@@ -444,10 +768,19 @@ impl AttributeHandler for TemplateMetadata {
 
 impl TxmlElement for TemplateMetadata {
     fn into_txml_element(self) -> String {
+        let tags = match self.tags.is_empty() {
+            true => String::new(),
+            false => format!(r#" tags="{}""#, self.tags.join(", ")),
+        };
+
         format!(
-            r#"<Metadata author="{}" date="{}" version="{}" description="{}"/>
+            r#"<Metadata author="{}" date="{}" version="{}" description="{}"{}/>
             "#,
-            self.author, self.date, self.version, self.description
+            self.author,
+            self.date,
+            self.version,
+            reverse_escape_xml(&self.description),
+            tags
         )
     }
 }
@@ -457,7 +790,7 @@ impl TxmlElement for TemplateMetadata {
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
-    use crate::txml_elements::{Directory, TemplateMetadata};
+    use crate::txml_elements::{Directory, Symlink, TemplateMetadata};
     use crate::txml_structure::TxmlStructure;
     use crate::TxmlElement;
 
@@ -468,8 +801,10 @@ mod tests {
             name: String::from("pepe"),
             out_command: String::from("git init"),
             in_command: String::from("ls -l"),
+            mode: String::new(),
             files: Vec::new(),
             directories: Vec::new(),
+            symlinks: Vec::new(),
         };
         
         let txml = dir.into_txml_element();
@@ -477,6 +812,64 @@ mod tests {
         assert_eq!(txml, "<Directory name=\"pepe\" in_command=\"ls -l\" out_command=\"git init\">\n</Directory>\n");
     }
     
+    #[test]
+    fn strip_commands_test()
+    {
+        let mut dir = Directory {
+            name: String::from("pepe"),
+            out_command: String::from("git init"),
+            in_command: String::from("ls -l"),
+            mode: String::new(),
+            files: Vec::new(),
+            directories: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        dir.add_file(crate::txml_elements::File {
+            name: String::from("pepe"),
+            extension: String::from("rs"),
+            command: String::from("cargo build"),
+            mode: String::new(),
+            content: String::new(),
+        });
+        dir.strip_commands();
+
+        assert_eq!(dir.into_txml_element(), "<Directory name=\"pepe\">\n<File name=\"pepe\" extension=\"rs\">\n\n</File>\n</Directory>\n");
+    }
+
+    #[test]
+    fn take_commands_test()
+    {
+        let mut dir = Directory {
+            name: String::from("pepe"),
+            out_command: String::from("git init"),
+            in_command: String::from("ls -l"),
+            mode: String::new(),
+            files: Vec::new(),
+            directories: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        dir.add_file(crate::txml_elements::File {
+            name: String::from("pepe"),
+            extension: String::from("rs"),
+            command: String::from("cargo build"),
+            mode: String::new(),
+            content: String::new(),
+        });
+
+        let mut commands = Vec::new();
+        dir.take_commands(std::path::Path::new(""), Some("app"), &mut commands);
+
+        let taken = commands
+            .iter()
+            .map(|command| (command.entry().to_str().unwrap(), command.command()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(taken, vec![("app", "ls -l"), ("app", "git init"), ("app", "cargo build")]);
+        assert_eq!(dir.into_txml_element(), "<Directory name=\"pepe\">\n<File name=\"pepe\" extension=\"rs\">\n\n</File>\n</Directory>\n");
+    }
+
     #[test]
     fn file_into_txml_format_test()
     {
@@ -484,6 +877,7 @@ mod tests {
             name: String::from("pepe"),
             extension: String::from("rs"),
             command: String::from("cargo build"),
+            mode: String::new(),
             content: String::from("fn main() { println!(\"Hola, mundo!\"); }"),
         };
         
@@ -501,14 +895,17 @@ mod tests {
             name: String::from("pepe"),
             out_command: String::from("git init"),
             in_command: String::from("ls -l"),
+            mode: String::new(),
             files: Vec::new(),
             directories: Vec::new(),
+            symlinks: Vec::new(),
         };
 
         let file = crate::txml_elements::File {
             name: String::from("pepe"),
             extension: String::from("rs"),
             command: String::from("cargo build"),
+            mode: String::new(),
             content: String::from("fn main() { println!(\"Hola, mundo!\"); }"),
         };
         
@@ -520,6 +917,7 @@ mod tests {
             name: String::from("pepa"),
             extension: String::from("rs"),
             command: String::from("cargo build"),
+            mode: String::new(),
             content: String::from("fn main() { println!(\"Hola, mundo!\"); }"),
         };
         
@@ -542,9 +940,17 @@ mod tests {
             date: String::from("22/09/2024"),
             version: String::from("1.0.0"),
             description: String::from("Testing metadata info"),
+            tags: Vec::new(),
         }.into_txml_element();
 
         assert_eq!(expected.trim(), metadata.trim());
+
+        let metadata = TemplateMetadata {
+            tags: vec![String::from("rust"), String::from("cli")],
+            ..TemplateMetadata::new()
+        }.into_txml_element();
+
+        assert_eq!(metadata.trim(), r#"<Metadata author="" date="" version="" description="" tags="rust, cli"/>"#);
     }
 
     #[test]
@@ -570,5 +976,123 @@ mod tests {
         assert_eq!(txml_structure.metadata().date, "22/09/2024");
         assert_eq!(txml_structure.metadata().version, "1.0.0");
         assert_eq!(txml_structure.metadata().description, "Testing metadata info");
+        assert!(txml_structure.metadata().tags.is_empty());
+
+        let metadata = TxmlStructure::obtain_metadata(r#"<Root><Metadata tags="rust, cli,,  web "/></Root>"#).unwrap();
+
+        assert_eq!(metadata.tags, vec!["rust", "cli", "web"]);
+
+        let mut txml_structure = TxmlStructure::new();
+        txml_structure.set_description(r#"A "quoted" & <odd> one"#);
+
+        let metadata = TxmlStructure::obtain_metadata(&txml_structure.into_txml_element()).unwrap();
+
+        assert_eq!(metadata.description, r#"A "quoted" & <odd> one"#);
+    }
+
+    #[test]
+    fn symlink_into_txml_format_test()
+    {
+        let symlink = Symlink {
+            name: String::from("latest"),
+            target: String::from("../releases/v1"),
+        };
+
+        let txml = symlink.into_txml_element();
+
+        assert_eq!(txml, "<Symlink name=\"latest\" target=\"../releases/v1\"/>\n");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn mode_into_txml_format_test()
+    {
+        let mut dir = Directory {
+            name: String::from("scripts"),
+            out_command: String::new(),
+            in_command: String::new(),
+            mode: String::from("700"),
+            files: Vec::new(),
+            directories: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        dir.add_file(crate::txml_elements::File {
+            name: String::from("gradlew"),
+            extension: String::new(),
+            command: String::new(),
+            mode: String::from("755"),
+            content: String::from("#!/bin/sh"),
+        });
+
+        let txml = dir.into_txml_element();
+
+        assert_eq!(txml, "<Directory name=\"scripts\" mode=\"700\">\n<File name=\"gradlew\" mode=\"755\">\n#!/bin/sh\n</File>\n</Directory>\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mode_and_symlink_round_trip_test()
+    {
+        use crate::{FsElement, Instantiable};
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("txml_mode_test_{}", std::process::id()));
+        let src = root.join("src");
+        let dst = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(src.join("scripts")).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("scripts/run.sh"), "echo hi").unwrap();
+        fs::set_permissions(src.join("scripts/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("scripts/run.sh", src.join("run")).unwrap();
+
+        let txml = Directory::from_path(&src).unwrap().into_txml_element();
+        assert!(txml.contains("<File name=\"run\" extension=\"sh\" mode=\"755\">"));
+        assert!(txml.contains("<Symlink name=\"run\" target=\"scripts/run.sh\"/>"));
+
+        let txml_structure = TxmlStructure::from_str(
+            format!("<Root>{}</Root>", txml).as_str()
+        ).unwrap();
+        txml_structure.instantiate(&dst);
+
+        let mode = fs::metadata(dst.join("src/scripts/run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(fs::read_link(dst.join("src/run")).unwrap().to_str(), Some("scripts/run.sh"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn escaped_names_round_trip_test()
+    {
+        use crate::{FsElement, Instantiable};
+        use std::fs;
+
+        let root = std::env::temp_dir().join(format!("txml_escape_test_{}", std::process::id()));
+        let src = root.join("src");
+        let dst = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+
+        fs::create_dir_all(src.join("Tom & Jerry")).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("Tom & Jerry/<\"notes\">.it's"), "content").unwrap();
+        std::os::unix::fs::symlink("Tom & Jerry/a&lt;b", src.join("link's")).unwrap();
+
+        let txml = Directory::from_path(&src).unwrap().into_txml_element();
+        assert!(txml.contains("<Directory name=\"Tom &amp; Jerry\" "));
+        assert!(txml.contains("<File name=\"&lt;&quot;notes&quot;&gt;\" extension=\"it&apos;s\""));
+        assert!(txml.contains("<Symlink name=\"link&apos;s\" target=\"Tom &amp; Jerry/a&amp;lt;b\"/>"));
+
+        let txml = format!("<Root>{}</Root>", txml);
+        assert!(TxmlStructure::validate_txml_str(&txml));
+        TxmlStructure::from_str(&txml).unwrap().instantiate(&dst);
+
+        assert_eq!(fs::read_to_string(dst.join("src/Tom & Jerry/<\"notes\">.it's")).unwrap().trim_end(), "content");
+        assert_eq!(fs::read_link(dst.join("src/link's")).unwrap().to_str(), Some("Tom & Jerry/a&lt;b"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,9 +1,11 @@
+use crate::commands::DeferredCommand;
 use crate::reader::{ElementState, TxmlEvent, TxmlReader, TxmlReaderError};
-use crate::txml_elements::{Directory, File, TemplateMetadata, Variable};
+use crate::txml_elements::{Directory, File, Symlink, TemplateMetadata, Variable};
+use crate::variables::{substitute, templatize_pairs};
 use crate::{AttributeHandler, FsElement, Instantiable, TxmlElement};
 use quick_xml::events::attributes::Attribute;
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::{fs, io};
 
@@ -18,15 +20,25 @@ pub enum TxmlProcessorError {
 pub struct TxmlStructure {
     files: Vec<File>,
     directories: Vec<Directory>,
+    symlinks: Vec<Symlink>,
+    variables: Vec<Variable>,
     metadata: TemplateMetadata,
     renamable: bool,
 }
 
+impl Default for TxmlStructure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TxmlStructure {
     pub fn new() -> TxmlStructure {
         TxmlStructure {
             files: Vec::new(),
             directories: Vec::new(),
+            symlinks: Vec::new(),
+            variables: Vec::new(),
             metadata: TemplateMetadata::new(),
             renamable: true,
         }
@@ -36,6 +48,10 @@ impl TxmlStructure {
         &self.metadata
     }
 
+    pub fn set_description(&mut self, description: &str) {
+        self.metadata.description = description.to_string();
+    }
+
     pub fn validate_txml_file(txml: &PathBuf) -> bool {
         if !txml.exists() {
             return false;
@@ -65,6 +81,13 @@ impl TxmlStructure {
     }
     
     pub fn from_txml_file(txml: &PathBuf) -> Result<TxmlStructure, TxmlProcessorError> {
+        Self::from_txml_file_with_values(txml, &HashMap::new())
+    }
+
+    pub fn from_txml_file_with_values(
+        txml: &PathBuf,
+        values: &HashMap<String, String>,
+    ) -> Result<TxmlStructure, TxmlProcessorError> {
         if !txml.exists() {
             return Err(TxmlProcessorError::InvalidDirectory);
         }
@@ -74,14 +97,22 @@ impl TxmlStructure {
 
         let txml_content = fs::read_to_string(txml).map_err(|_| TxmlProcessorError::BinaryFileError)?;
 
-        Self::from_str(txml_content.as_str())
+        Self::from_str_with_values(txml_content.as_str(), values)
+    }
+
+    pub fn from_path(path: &Path) -> Result<TxmlStructure, io::Error> {
+        Self::from_path_filtered(path, &|_| true)
     }
 
-    pub fn from_path(path: &PathBuf) -> Result<TxmlStructure, io::Error> {
+    /// Same as `from_path` but skipping the entries inside the directory for which `filter`
+    /// returns false.
+    pub fn from_path_filtered(path: &Path, filter: &dyn Fn(&Path) -> bool) -> Result<TxmlStructure, io::Error> {
         let mut txml_structure = TxmlStructure::new();
 
-        if path.is_dir() {
-            txml_structure.add_directory(Directory::from_path(path)?)
+        if path.is_symlink() {
+            txml_structure.add_symlink(Symlink::from_path(path)?)
+        } else if path.is_dir() {
+            txml_structure.add_directory(Directory::from_path_filtered(path, filter)?)
         } else {
             txml_structure.add_file(File::from_path(path)?)
         }
@@ -89,6 +120,27 @@ impl TxmlStructure {
         Ok(txml_structure)
     }
 
+    /// Reads the Metadata of a TXML without resolving its variables.
+    pub fn obtain_metadata(txml: &str) -> Result<TemplateMetadata, TxmlProcessorError> {
+        let mut metadata = TemplateMetadata::new();
+        let mut reader = TxmlReader::from_str(txml);
+
+        loop {
+            match reader.read_event() {
+                Ok(TxmlEvent::Metadata(ElementState::Start(bytes) | ElementState::Empty(bytes))) => {
+                    bytes.attributes().for_each(|attr| {
+                        metadata.process_attribute(attr.expect("Error reading attribute"))
+                    });
+                }
+                Ok(TxmlEvent::Eof) => break,
+                Err(_e) => return Err(TxmlProcessorError::UnknownParseError),
+                _ => continue,
+            }
+        }
+
+        Ok(metadata)
+    }
+
     pub fn obtain_variables(fxml: &str) -> Result<Vec<Variable>, TxmlProcessorError>
     {
         let mut variables = Vec::new();
@@ -97,17 +149,14 @@ impl TxmlStructure {
 
         loop {
             match reader.read_event() {
-                Ok(TxmlEvent::Variable(state)) => match state {
-                    ElementState::Start(bytes) | ElementState::Empty(bytes) => {
-                        let mut variable = Variable::new();
-                        
-                        bytes.attributes().for_each(|attr| {
-                            variable.process_attribute(attr.expect("Error reading attribute"))
-                        });
-                        
-                        variables.push(variable);
-                    }
-                    _ => continue,
+                Ok(TxmlEvent::Variable(ElementState::Start(bytes) | ElementState::Empty(bytes))) => {
+                    let mut variable = Variable::new();
+
+                    bytes.attributes().for_each(|attr| {
+                        variable.process_attribute(attr.expect("Error reading attribute"))
+                    });
+
+                    variables.push(variable);
                 }
                 Ok(TxmlEvent::Eof) => break,
                 Err(_e) => return Err(TxmlProcessorError::UnknownParseError),
@@ -125,13 +174,56 @@ impl TxmlStructure {
     pub fn add_directory(&mut self, directory: Directory) {
         self.directories.push(directory);
     }
+
+    pub fn add_symlink(&mut self, symlink: Symlink) {
+        self.symlinks.push(symlink);
+    }
+
+    pub fn add_variable(&mut self, variable: Variable) {
+        self.variables.push(variable);
+    }
+
+    /// Turns concrete values into variables. Every `(literal, variable)` pair replaces the literal
+    /// in names and contents by `${variable}` and declares the variable. With `detect_cases`
+    /// the case variants of the literal are replaced by `${variable:case}` too.
+    pub fn templatize(&mut self, values: &[(&str, &str)], detect_cases: bool) {
+        let pairs = templatize_pairs(values, detect_cases);
+
+        self.files.iter_mut().for_each(|file| file.templatize(&pairs));
+        self.directories.iter_mut().for_each(|directory| directory.templatize(&pairs));
+        self.symlinks.iter_mut().for_each(|symlink| symlink.templatize(&pairs));
+
+        for (_, name) in values {
+            if self.variables.iter().all(|variable| variable.get_name() != *name) {
+                self.add_variable(Variable::named(name));
+            }
+        }
+    }
+
+    /// Drops every command of the template, so instantiating it only creates files.
+    pub fn strip_commands(&mut self) {
+        self.files.iter_mut().for_each(File::strip_commands);
+        self.directories.iter_mut().for_each(Directory::strip_commands);
+    }
+
+    /// Drops every command of the template and returns them, in the order they would run when
+    /// instantiated with the name, so they can be run once the files are in place.
+    pub fn take_commands(&mut self, name: &str) -> Vec<DeferredCommand> {
+        let renamed = self.files.len() + self.directories.len() + self.symlinks.len() == 1 && self.renamable;
+        let name = Some(name).filter(|_| renamed);
+        let mut commands = Vec::new();
+
+        self.files.iter_mut().for_each(|file| file.take_commands(Path::new(""), name, &mut commands));
+        self.directories.iter_mut().for_each(|directory| directory.take_commands(Path::new(""), name, &mut commands));
+
+        commands
+    }
 }
 
 impl AttributeHandler for TxmlStructure {
     fn process_attribute(&mut self, attr: Attribute) {
-        match attr.key.0 {
-            b"renamable" => self.renamable = String::from_utf8_lossy(&attr.value).to_string() == "true",
-            _ => (),
+        if attr.key.0 == b"renamable" {
+            self.renamable = String::from_utf8_lossy(&attr.value) == "true";
         }
     }
 }
@@ -147,6 +239,10 @@ impl TxmlElement for TxmlStructure {
         );
         
         txml_content += self.metadata.into_txml_element().as_str();
+
+        for variable in self.variables {
+            txml_content += variable.into_txml_element().as_str();
+        }
         
         for file in self.files {
             txml_content += file.into_txml_element().as_str();
@@ -156,6 +252,10 @@ impl TxmlElement for TxmlStructure {
             txml_content += directory.into_txml_element().as_str();
         }
 
+        for symlink in self.symlinks {
+            txml_content += symlink.into_txml_element().as_str();
+        }
+
         txml_content += "</Root>";
 
         txml_content
@@ -166,23 +266,26 @@ impl FromStr for TxmlStructure {
     type Err = TxmlProcessorError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_with_values(s, &HashMap::new())
+    }
+}
+
+impl TxmlStructure {
+    /// Parses a TXML, resolving its variables with the given values first. The variables
+    /// without one keep their own value or are asked in the stdin.
+    pub fn from_str_with_values(s: &str, values: &HashMap<String, String>) -> Result<Self, TxmlProcessorError> {
         let mut txml_structure = TxmlStructure::new();
 
         let vars = Self::obtain_variables(s)?;
         let mut s = s.to_string();
         
         for var in vars {
-            let value = if var.get_value().is_empty() {
-                println!("Please, introduce the value for the variable '{}'", var.get_name());
-                let mut value = String::new();
-                io::stdin().read_line(&mut value).expect("Error reading from stdin");
-                value.trim().to_string()
-            } else {
-                var.get_value().to_string()
+            let value = match values.get(var.get_name()) {
+                Some(value) => value.clone(),
+                None => var.resolve_value(),
             };
-            
-            let variable_expression = format!("${{{}}}", var.get_name());
-            s = s.replace(variable_expression.as_str(), value.as_str());
+
+            s = substitute(&s, var.get_name(), &value);
         }
         
         let mut reader = TxmlReader::from_str(&s);
@@ -286,6 +389,24 @@ impl FromStr for TxmlStructure {
                         }
                     }
                 }
+                Ok(TxmlEvent::Symlink(state)) => match state {
+                    ElementState::Start(bytes) | ElementState::Empty(bytes) => {
+                        let mut symlink = Symlink::new();
+                        bytes.attributes().for_each(|attr| {
+                            symlink.process_attribute(attr.expect("Error reading attribute"));
+                        });
+
+                        if dir_queue.is_empty() {
+                            txml_structure.add_symlink(symlink)
+                        } else {
+                            dir_queue
+                                .back_mut()
+                                .expect("Shouldn't be empty")
+                                .add_symlink(symlink)
+                        }
+                    }
+                    ElementState::End => continue,
+                }
                 Ok(TxmlEvent::Text(e)) => {
                     if let Some(ref mut file) = current_file {
                         let content = String::from_utf8_lossy(&e).to_string();
@@ -310,16 +431,20 @@ impl FromStr for TxmlStructure {
 }
 
 impl Instantiable for TxmlStructure {
-    fn instantiate(&self, dir: &PathBuf) {
-        self.files.iter().for_each(|file| file.instantiate(&dir));
+    fn instantiate(&self, dir: &Path) {
+        self.files.iter().for_each(|file| file.instantiate(dir));
 
         self.directories
             .iter()
             .for_each(|directory| directory.instantiate(dir));
+
+        self.symlinks
+            .iter()
+            .for_each(|symlink| symlink.instantiate(dir));
     }
 
-    fn instantiate_with_name(&self, dir: &PathBuf, name: &str) {
-        if self.files.len() + self.directories.len() > 1 || !self.renamable {
+    fn instantiate_with_name(&self, dir: &Path, name: &str) {
+        if self.files.len() + self.directories.len() + self.symlinks.len() > 1 || !self.renamable {
             self.instantiate(dir);
             return;
         }
@@ -333,12 +458,17 @@ impl Instantiable for TxmlStructure {
             self.directories[0].instantiate_with_name(dir, name);
             return;
         }
+
+        if self.symlinks.len() == 1 {
+            self.symlinks[0].instantiate_with_name(dir, name);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::txml_structure::TxmlStructure;
+    use crate::TxmlElement;
     use std::str::FromStr;
 
     #[test]
@@ -403,4 +533,43 @@ mod tests {
         assert_eq!(txml_variables[1].get_name(), "VAR2");
         assert_eq!(txml_variables[1].get_value(), "file1");
     }
+
+    #[test]
+    fn txml_variables_with_values_test() {
+        let txml = r#"
+<Root>
+    <Variable name="VAR1" value="folder1"/>
+    <Directory name="${VAR1}"/>
+</Root>
+        "#;
+
+        let values = [(String::from("VAR1"), String::from("given"))].into_iter().collect();
+        let txml = TxmlStructure::from_str_with_values(txml, &values).unwrap().into_txml_element();
+
+        assert!(txml.contains(r#"<Directory name="given""#));
+    }
+
+    #[test]
+    fn txml_templatize_test() {
+        let mut txml_structure = TxmlStructure::from_str(r#"
+<Root>
+    <Directory name="my-proj">
+        <File name="my_proj" extension="rs">
+            struct MyProj;
+        </File>
+    </Directory>
+</Root>
+        "#).unwrap();
+
+        txml_structure.templatize(&[("my-proj", "project_name")], true);
+
+        let txml = txml_structure.into_txml_element();
+        let variables = TxmlStructure::obtain_variables(&txml).unwrap();
+
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].get_name(), "project_name");
+        assert!(txml.contains(r#"<Directory name="${project_name}">"#));
+        assert!(txml.contains(r#"<File name="${project_name:snake}" extension="rs">"#));
+        assert!(txml.contains("struct ${project_name:pascal};"));
+    }
 }
@@ -0,0 +1,212 @@
+use std::str::FromStr;
+
+/// Case conversions that can be applied to a variable with the `${name:case}` syntax.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Lower,
+    Upper,
+    Snake,
+    Kebab,
+    Camel,
+    Pascal,
+    UpperSnake,
+}
+
+impl Case {
+    pub const ALL: [Case; 7] = [
+        Case::Lower,
+        Case::Upper,
+        Case::Snake,
+        Case::Kebab,
+        Case::Camel,
+        Case::Pascal,
+        Case::UpperSnake,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Case::Lower => "lower",
+            Case::Upper => "upper",
+            Case::Snake => "snake",
+            Case::Kebab => "kebab",
+            Case::Camel => "camel",
+            Case::Pascal => "pascal",
+            Case::UpperSnake => "upper_snake",
+        }
+    }
+
+    pub fn apply(&self, value: &str) -> String {
+        let words = split_words(value);
+
+        match self {
+            Case::Lower => value.to_lowercase(),
+            Case::Upper => value.to_uppercase(),
+            Case::Snake => words.join("_"),
+            Case::Kebab => words.join("-"),
+            Case::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| if i == 0 { word.clone() } else { capitalize(word) })
+                .collect(),
+            Case::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            Case::UpperSnake => words.join("_").to_uppercase(),
+        }
+    }
+}
+
+impl FromStr for Case {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Case::ALL.into_iter().find(|case| case.name() == s).ok_or(())
+    }
+}
+
+/// Replaces `${name}` and every `${name:case}` in the text with the value of the variable.
+pub fn substitute(text: &str, name: &str, value: &str) -> String {
+    let mut result = text.replace(format!("${{{}}}", name).as_str(), value);
+
+    for case in Case::ALL {
+        let expression = format!("${{{}:{}}}", name, case.name());
+
+        if result.contains(expression.as_str()) {
+            result = result.replace(expression.as_str(), case.apply(value).as_str());
+        }
+    }
+
+    result
+}
+
+/// Same as `substitute` for every `(name, value)` pair.
+pub fn substitute_all(text: &str, variables: &[(String, String)]) -> String {
+    variables
+        .iter()
+        .fold(text.to_string(), |text, (name, value)| substitute(&text, name, value))
+}
+
+/// Builds the variable expressions that will replace each literal. When `detect_cases` is
+/// set, the case variants of the literal (e.g. `MyProj`, `my_proj`) are also replaced by the
+/// matching `${name:case}` expression. Longer literals come first so they take precedence.
+pub fn templatize_pairs(values: &[(&str, &str)], detect_cases: bool) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+
+    for (literal, name) in values {
+        if literal.is_empty() {
+            continue;
+        }
+
+        pairs.push((literal.to_string(), format!("${{{}}}", name)));
+
+        if !detect_cases {
+            continue;
+        }
+
+        for case in Case::ALL {
+            let variant = case.apply(literal);
+
+            if !variant.is_empty() && pairs.iter().all(|(l, _)| *l != variant) {
+                pairs.push((variant, format!("${{{}:{}}}", name, case.name())));
+            }
+        }
+    }
+
+    pairs.sort_by_key(|pair| std::cmp::Reverse(pair.0.len()));
+    pairs
+}
+
+/// Replaces every literal of the pairs in a single pass, so the text inserted for one literal
+/// is never replaced again by a shorter one.
+pub fn replace_literals(text: &str, pairs: &[(String, String)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    'outer: while let Some(c) = rest.chars().next() {
+        for (literal, expression) in pairs {
+            if let Some(remaining) = rest.strip_prefix(literal.as_str()) {
+                result.push_str(expression);
+                rest = remaining;
+                continue 'outer;
+            }
+        }
+
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    result
+}
+
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+
+    for c in value.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+
+        previous_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_conversion_test() {
+        assert_eq!(Case::Pascal.apply("my-proj"), "MyProj");
+        assert_eq!(Case::Camel.apply("my_proj"), "myProj");
+        assert_eq!(Case::Snake.apply("MyProj"), "my_proj");
+        assert_eq!(Case::Kebab.apply("Acme Corp"), "acme-corp");
+        assert_eq!(Case::UpperSnake.apply("myProj"), "MY_PROJ");
+        assert_eq!(Case::from_str("upper_snake"), Ok(Case::UpperSnake));
+    }
+
+    #[test]
+    fn substitute_test() {
+        let text = "${name} ${name:pascal} ${name:upper_snake} ${other}";
+
+        assert_eq!(substitute(text, "name", "my-proj"), "my-proj MyProj MY_PROJ ${other}");
+    }
+
+    #[test]
+    fn replace_literals_test() {
+        let pairs = templatize_pairs(&[("my-proj", "project_name"), ("Acme Corp", "company")], true);
+        let text = "my-proj MyProj my_proj MY_PROJ by Acme Corp";
+
+        assert_eq!(
+            replace_literals(text, &pairs),
+            "${project_name} ${project_name:pascal} ${project_name:snake} ${project_name:upper_snake} by ${company}"
+        );
+
+        let pairs = templatize_pairs(&[("proj", "project"), ("myproj", "name")], false);
+
+        assert_eq!(replace_literals("myproj/proj", &pairs), "${name}/${project}");
+    }
+}
@@ -6,7 +6,10 @@ TEMPLATES_DIR="$HOME/.mkt-dev";
 TEST_DIR="tests/";
 DIR_TEMPLATE_TEST="test_dir_template.sh";
 GIT_TEMPLATE_TEST="test_git_template.sh";
+GIT_CACHE_TEST="test_git_cache.sh";
 TXML_TEMPLATE_TEST="test_txml_template.sh";
+DOCTOR_TEST="test_doctor.sh";
+BUNDLE_TEST="test_bundle.sh";
 BINARY_APP="mkt_dev";
 RESOURCES_DIR="resources";
 
@@ -18,7 +21,10 @@ reset_app_dir()
     if [[ "$archivo" == "./." || "$archivo" == "./.." || \
           "$archivo" == "./$DIR_TEMPLATE_TEST" || \
           "$archivo" == "./$GIT_TEMPLATE_TEST" || \
+          "$archivo" == "./$GIT_CACHE_TEST" || \
           "$archivo" == "./$TXML_TEMPLATE_TEST" || \
+          "$archivo" == "./$DOCTOR_TEST" || \
+          "$archivo" == "./$BUNDLE_TEST" || \
           "$archivo" == "./$BINARY_APP" || \
           "$archivo" == "./$RESOURCES_DIR" ]]; then
         continue # Don't remove files needed for testing
@@ -61,6 +67,16 @@ fi
 
 reset_app_dir;
 
+### Execute Git cache test
+
+if ! bash "$GIT_CACHE_TEST"; then
+  echo -e "=> ${FAILED}: Git cache didn't pass the tests"
+else
+  echo -e "=> ${SUCCESS}: Git cache passed the tests"
+fi
+
+reset_app_dir;
+
 ### Execute TXML Template test
 
 if ! bash "$TXML_TEMPLATE_TEST"; then
@@ -71,4 +87,24 @@ fi
 
 reset_app_dir;
 
+### Execute Doctor test
+
+if ! bash "$DOCTOR_TEST"; then
+  echo -e "=> ${FAILED}: Doctor didn't pass the tests"
+else
+  echo -e "=> ${SUCCESS}: Doctor passed the tests"
+fi
+
+reset_app_dir;
+
+### Execute Bundle test
+
+if ! bash "$BUNDLE_TEST"; then
+  echo -e "=> ${FAILED}: Bundles didn't pass the tests"
+else
+  echo -e "=> ${SUCCESS}: Bundles passed the tests"
+fi
+
+reset_app_dir;
+
 rm "$BINARY_APP"
\ No newline at end of file
@@ -0,0 +1,86 @@
+#!/bin/bash
+
+source resources/utils.sh
+
+mkdir bundle_dir
+echo "content" > bundle_dir/file
+
+$APP_BINARY add -p bundle_dir -as-dir
+$APP_BINARY add -p resources/template_example_0.xml -n bundle_txml
+
+### Testing export command
+
+$APP_BINARY export -n bundle_dir -n bundle_txml -o bundle.tar.gz
+
+if [ ! -f bundle.tar.gz ]; then
+  echo -e "${FAILED}: Export command didn't create the bundle"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Export command passed the tests"
+
+### Testing import command
+
+$APP_BINARY rm -n bundle_dir
+$APP_BINARY import bundle.tar.gz
+
+if [ ! -f "$TEMPLATES_DIR/bundle_dir/payload/file" ] || \
+   ! cmp -s resources/template_example_0.xml "$TEMPLATES_DIR/bundle_txml/payload/template.txml"; then
+  echo -e "${FAILED}: Import command didn't import the templates"
+  exit 1
+fi
+
+$APP_BINARY import bundle.tar.gz --rename bundle_dir=bundle_renamed
+
+if [ ! -f "$TEMPLATES_DIR/bundle_renamed/payload/file" ] || \
+   ! grep -q '"data_path": "bundle_renamed/payload"' "$TEMPLATES_DIR/bundle_renamed/template.json"; then
+  echo -e "${FAILED}: Import command with --rename didn't rename the template"
+  exit 1
+fi
+
+echo "changed" > "$TEMPLATES_DIR/bundle_dir/payload/file"
+$APP_BINARY import bundle.tar.gz -r
+
+if [ "$(cat "$TEMPLATES_DIR/bundle_dir/payload/file")" != "content" ] || \
+   [ ! -f "$TEMPLATES_DIR/bundle_txml/payload/template.txml" ]; then
+  echo -e "${FAILED}: Import command with -r didn't replace the templates"
+  exit 1
+fi
+
+$APP_BINARY spawn -n bundle_renamed -o bundle_spawned
+
+if [ "$(cat bundle_spawned/file 2>/dev/null)" != "content" ]; then
+  echo -e "${FAILED}: The imported template couldn't be spawned"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Import command passed the tests"
+
+### Testing import of a bundle whose payload path leaves the store
+
+mkdir -p evil_bundle victim
+echo "keep" > victim/file
+tar -xzf bundle.tar.gz -C evil_bundle
+sed -i "s|\"data_path\": \"bundle_dir/payload\"|\"data_path\": \"$(pwd)/victim\"|" evil_bundle/bundle_dir/template.json
+tar -czf evil.tar.gz -C evil_bundle .
+
+$APP_BINARY import evil.tar.gz --rename bundle_dir=bundle_evil
+$APP_BINARY rm -n bundle_evil
+
+if [ -d "$TEMPLATES_DIR/bundle_evil" ] || [ ! -f victim/file ]; then
+  echo -e "${FAILED}: Import command accepted a payload path outside the store"
+  exit 1
+fi
+
+sed -i "s|\"data_path\": \"[^\"]*\"|\"data_path\": \"../../victim\"|" evil_bundle/bundle_dir/template.json
+tar -czf evil.tar.gz -C evil_bundle .
+
+$APP_BINARY import evil.tar.gz --rename bundle_dir=bundle_evil
+$APP_BINARY rm -n bundle_evil
+
+if [ -d "$TEMPLATES_DIR/bundle_evil" ] || [ ! -f victim/file ]; then
+  echo -e "${FAILED}: Import command accepted a relative payload path outside the store"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Import command refused payload paths outside the store"
@@ -15,13 +15,13 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/test_dir" ]; then
+if [ ! -f "$TEMPLATES_DIR/test_dir/template.json" ]; then
   echo -e "${FAILED}: Dir Template add command without name didn't create the template data"
   exit 1
 fi
 
-if [ ! -d "$TEMPLATES_DIR/test_dir.dir" ]; then
-  echo -e "${FAILED}: Dir Template add command without name didn't create the .dir directory"
+if [ ! -d "$TEMPLATES_DIR/test_dir/payload" ]; then
+  echo -e "${FAILED}: Dir Template add command without name didn't create the payload directory"
   exit 1
 fi
 
@@ -36,13 +36,13 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/test_dir_named" ]; then
+if [ ! -f "$TEMPLATES_DIR/test_dir_named/template.json" ]; then
   echo -e "${FAILED}: Dir Template add command with name didn't create the template data"
   exit 1
 fi
 
-if [ ! -d "$TEMPLATES_DIR/test_dir_named.dir" ]; then
-  echo -e "${FAILED}: Dir Template add command with name didn't create the .dir directory"
+if [ ! -d "$TEMPLATES_DIR/test_dir_named/payload" ]; then
+  echo -e "${FAILED}: Dir Template add command with name didn't create the payload directory"
   exit 1
 fi
 
@@ -82,6 +82,98 @@ fi
 
 echo -e "${SUCCESS}: Dir Template spawn command with custom name passed the tests"
 
+### Testing add command with an archive
+
+mkdir -p archive_src/starter-main/src
+echo "archived" > archive_src/starter-main/src/file
+tar -czf starter.tar.gz -C archive_src starter-main
+
+$APP_BINARY add -p starter.tar.gz -as-dir
+$APP_BINARY spawn -n starter -o starter_spawned
+
+if [ "$(cat starter_spawned/src/file 2>/dev/null)" != "archived" ]; then
+  echo -e "${FAILED}: Dir Template add command with an archive didn't strip the top-level folder"
+  exit 1
+fi
+
+$APP_BINARY rm -n starter
+
+$APP_BINARY add -p starter.tar.gz -n starter_root --keep-root
+
+if ! grep -q '<Directory name="starter"' "$TEMPLATES_DIR/starter_root/payload/template.txml"; then
+  echo -e "${FAILED}: Dir Template add command with an archive didn't name the kept root after the archive"
+  exit 1
+fi
+
+$APP_BINARY rm -n starter_root
+
+echo -e "${SUCCESS}: Dir Template add command with an archive passed the tests"
+
+### Testing the mkt.toml manifest
+
+# A mkt.toml of the project that isn't a manifest is kept as a file
+
+mkdir foreign_manifest
+printf '[package]\nname = "app"\n' > foreign_manifest/mkt.toml
+
+$APP_BINARY add -p foreign_manifest -n foreign_txml
+$APP_BINARY add -p foreign_manifest -n foreign_dir -as-dir
+$APP_BINARY spawn -n foreign_txml -o foreign_txml_spawned
+$APP_BINARY spawn -n foreign_dir -o foreign_dir_spawned
+
+if ! grep -q 'name = "app"' foreign_txml_spawned/mkt.toml 2>/dev/null || \
+   ! grep -q 'name = "app"' foreign_dir_spawned/mkt.toml 2>/dev/null; then
+  echo -e "${FAILED}: Dir Template add command failed with a mkt.toml that isn't a manifest"
+  exit 1
+fi
+
+$APP_BINARY rm -n foreign_txml
+$APP_BINARY rm -n foreign_dir
+
+# The description of the manifest is kept when converted to TXML
+
+mkdir described_dir
+printf 'description = "A described starter"\n' > described_dir/mkt.toml
+
+$APP_BINARY add -p described_dir
+
+if ! $APP_BINARY list -d | grep -q "A described starter"; then
+  echo -e "${FAILED}: Dir Template add command dropped the description of the manifest"
+  exit 1
+fi
+
+$APP_BINARY rm -n described_dir
+
+# An invalid manifest fails the add instead of saving the template as a directory
+
+mkdir invalid_manifest
+printf 'description = 3\n' > invalid_manifest/mkt.toml
+
+$APP_BINARY add -p invalid_manifest
+
+if [ -d "$TEMPLATES_DIR/invalid_manifest" ]; then
+  echo -e "${FAILED}: Dir Template add command saved a directory with an invalid manifest"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Dir Template mkt.toml manifest passed the tests"
+
+### Testing mv and cp commands
+
+$APP_BINARY mv test_dir test_dir_moved
+$APP_BINARY cp test_dir_moved test_dir
+
+if [ -d "$TEMPLATES_DIR/test_dir_moved/payload" ] && [ -d "$TEMPLATES_DIR/test_dir/payload" ] && \
+   grep -q '"data_path": "test_dir_moved/payload"' "$TEMPLATES_DIR/test_dir_moved/template.json" && \
+   grep -q '"data_path": "test_dir/payload"' "$TEMPLATES_DIR/test_dir/template.json"; then
+  echo -e "${SUCCESS}: Dir Template mv and cp commands passed the tests"
+else
+  echo -e "${FAILED}: Dir Template mv and cp commands didn't move and copy the template"
+  exit 1
+fi
+
+$APP_BINARY rm -n test_dir_moved
+
 ### Testing remove command
 
 $APP_BINARY rm -n test_dir
@@ -91,14 +183,30 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ -f "$TEMPLATES_DIR/test_dir" ]; then
+if [ -f "$TEMPLATES_DIR/test_dir/template.json" ]; then
   echo -e "${FAILED}: Dir Template remove command didn't remove the template data"
   exit 1
 fi
 
-if [ -d "$TEMPLATES_DIR/test_dir.dir" ]; then
-  echo -e "${FAILED}: Dir Template remove command didn't remove the .dir directory"
+if [ -d "$TEMPLATES_DIR/test_dir/payload" ]; then
+  echo -e "${FAILED}: Dir Template remove command didn't remove the payload directory"
   exit 1
 fi
 
 echo -e "${SUCCESS}: Dir Template remove command passed the tests"
+
+# A payload path edited to point outside the store is never deleted
+
+mkdir -p outside_payload
+echo "keep" > outside_payload/file
+
+$APP_BINARY add -p test_dir -n edited_dir -as-dir
+sed -i "s|\"data_path\": \"[^\"]*\"|\"data_path\": \"$(pwd)/outside_payload\"|" "$TEMPLATES_DIR/edited_dir/template.json"
+$APP_BINARY rm -n edited_dir
+
+if [ ! -f outside_payload/file ]; then
+  echo -e "${FAILED}: Dir Template remove command deleted a payload outside the store"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Dir Template remove command kept the payload outside the store"
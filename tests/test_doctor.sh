@@ -0,0 +1,84 @@
+#!/bin/bash
+
+source resources/utils.sh
+
+QUARANTINE_DIR="$HOME/.mkt-dev/quarantine"
+
+mkdir doctor_dir
+echo "content" > doctor_dir/file
+
+$APP_BINARY add -p doctor_dir -as-dir
+$APP_BINARY add -p resources/template_example_0.xml -n invalid_txml
+
+# Breaking the store: a stray file, a lost data file, an unknown class, a payload of an older
+# version without data and a TXML payload that isn't valid
+
+echo "stray" > "$TEMPLATES_DIR/stray_file"
+rm "$TEMPLATES_DIR/doctor_dir/template.json"
+mkdir "$TEMPLATES_DIR/unknown_class"
+echo '{"class": "unknown", "data_path": "unknown_class/payload"}' > "$TEMPLATES_DIR/unknown_class/template.json"
+cp resources/template_example_0.xml "$TEMPLATES_DIR/orphan.txml"
+echo "<Root></File>" > "$TEMPLATES_DIR/invalid_txml/payload/template.txml"
+
+### Testing list command with broken entries
+
+if ! $APP_BINARY list > /dev/null; then
+  echo -e "${FAILED}: List command failed with broken entries in the store"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: List command passed the tests with broken entries"
+
+### Testing doctor command
+
+if [ "$($APP_BINARY doctor | grep -c "stray_file\|doctor_dir\|unknown_class\|orphan.txml\|invalid_txml")" -ne 5 ]; then
+  echo -e "${FAILED}: Doctor command didn't report the broken entries"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Doctor command reported the broken entries"
+
+### Testing doctor --fix command
+
+$APP_BINARY rm -n invalid_txml
+
+$APP_BINARY doctor --fix
+
+if [ ! -f "$TEMPLATES_DIR/doctor_dir/template.json" ] || \
+   [ ! -f "$QUARANTINE_DIR/stray_file" ] || \
+   [ ! -d "$QUARANTINE_DIR/unknown_class" ] || \
+   [ ! -f "$TEMPLATES_DIR/orphan/payload/template.txml" ] || \
+   [ -e "$TEMPLATES_DIR/unknown_class" ]; then
+  echo -e "${FAILED}: Doctor --fix command didn't repair or quarantine the broken entries"
+  exit 1
+fi
+
+if [ "$($APP_BINARY doctor)" != "No problems found." ]; then
+  echo -e "${FAILED}: Doctor --fix command left problems behind"
+  exit 1
+fi
+
+$APP_BINARY spawn -n doctor_dir -o doctor_spawned
+
+if [ "$(cat doctor_spawned/file 2>/dev/null)" != "content" ]; then
+  echo -e "${FAILED}: The repaired template couldn't be spawned"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Doctor --fix command passed the tests"
+
+### Testing data written by a newer version
+
+DATA_FILE="$TEMPLATES_DIR/doctor_dir/template.json"
+sed -i 's/"schema_version": [0-9]*/"schema_version": 999, "future_field": "kept"/' "$DATA_FILE"
+cp "$DATA_FILE" newer_template.json
+
+$APP_BINARY list > /dev/null
+$APP_BINARY spawn -n doctor_dir -o doctor_newer > /dev/null
+
+if ! cmp -s "$DATA_FILE" newer_template.json || [ "$(cat doctor_newer/file 2>/dev/null)" != "content" ]; then
+  echo -e "${FAILED}: Data written by a newer version was rewritten"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Data written by a newer version passed the tests"
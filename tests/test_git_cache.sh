@@ -0,0 +1,131 @@
+#!/bin/bash
+
+source resources/utils.sh
+
+GIT_CACHE_DIR="$HOME/.mkt-dev/cache/git"
+GIT="git -c user.name=mkt -c user.email=mkt@localhost"
+
+# A local bare repository so the cache can be tested without internet
+
+mkdir cache_src
+$GIT -C cache_src init --quiet
+echo "v1" > cache_src/VERSION
+$GIT -C cache_src add VERSION
+$GIT -C cache_src commit --quiet -m "v1"
+git clone --quiet --bare cache_src cache_repo.git
+
+### Testing add command
+
+$APP_BINARY add -p cache_repo.git -n cached
+
+if [ $? -ne 0 ] || [ -z "$(ls -A "$GIT_CACHE_DIR" 2>/dev/null)" ]; then
+  echo -e "${FAILED}: Git Template add command didn't create the mirror"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template add command created the mirror"
+
+### Testing offline spawn
+
+mv cache_repo.git cache_repo_moved.git
+
+$APP_BINARY spawn -n cached -o cached_offline --offline
+
+if [ "$(cat cached_offline/VERSION 2>/dev/null)" != "v1" ]; then
+  echo -e "${FAILED}: Git Template offline spawn didn't use the mirror"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template offline spawn passed the tests"
+
+# Without --offline the mirror is fetched, an unreachable repository only warns
+
+$APP_BINARY spawn -n cached -o cached_unreachable 2> unreachable_stderr
+
+if [ "$(cat cached_unreachable/VERSION 2>/dev/null)" != "v1" ] || ! grep -q "couldn't be fetched" unreachable_stderr; then
+  echo -e "${FAILED}: Git Template spawn didn't fall back to the mirror of an unreachable repository"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template spawn of an unreachable repository passed the tests"
+
+mv cache_repo_moved.git cache_repo.git
+
+### Testing fresh history
+
+# Without a git identity the first commit is still made
+
+GIT_CONFIG_GLOBAL=/dev/null GIT_CONFIG_NOSYSTEM=1 $APP_BINARY spawn -n cached -o cached_fresh --history fresh
+
+if [ "$(git -C cached_fresh rev-list --count HEAD 2>/dev/null)" != "1" ]; then
+  echo -e "${FAILED}: Git Template spawn with a fresh history didn't make the first commit"
+  exit 1
+fi
+
+# A confirmed spawn makes the first commit once the files are moved
+
+echo "y" | $APP_BINARY spawn -n cached -o cached_confirmed --history fresh --confirm
+
+if [ "$(git -C cached_confirmed rev-list --count HEAD 2>/dev/null)" != "1" ]; then
+  echo -e "${FAILED}: Git Template confirmed spawn with a fresh history didn't make the first commit"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template spawn with a fresh history passed the tests"
+
+### Testing update command
+
+echo "v2" > cache_src/VERSION
+$GIT -C cache_src commit --quiet -am "v2"
+git -C cache_src push --quiet ../cache_repo.git HEAD
+
+$APP_BINARY spawn -n cached -o cached_fetched
+
+if [ "$(cat cached_fetched/VERSION 2>/dev/null)" != "v2" ]; then
+  echo -e "${FAILED}: Git Template spawn didn't fetch the new commits into the mirror"
+  exit 1
+fi
+
+echo "v3" > cache_src/VERSION
+$GIT -C cache_src commit --quiet -am "v3"
+git -C cache_src push --quiet ../cache_repo.git HEAD
+
+$APP_BINARY spawn -n cached -o cached_stale --offline
+
+if [ "$(cat cached_stale/VERSION 2>/dev/null)" != "v2" ]; then
+  echo -e "${FAILED}: Git Template offline spawn didn't clone from the mirror"
+  exit 1
+fi
+
+$APP_BINARY update -n cached
+$APP_BINARY spawn -n cached -o cached_updated --offline
+
+if [ "$(cat cached_updated/VERSION 2>/dev/null)" != "v3" ]; then
+  echo -e "${FAILED}: Git Template update command didn't fetch the new commits"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template update command passed the tests"
+
+### Testing shallow clones
+
+$APP_BINARY spawn -n cached -o cached_shallow --ref "$(git -C cache_src branch --show-current)" --offline
+
+if [ "$(git -C cached_shallow rev-list --count HEAD 2>/dev/null)" != "1" ] || \
+   [ "$(cat cached_shallow/VERSION 2>/dev/null)" != "v3" ]; then
+  echo -e "${FAILED}: Git Template spawn of a branch wasn't a shallow clone"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template shallow clone passed the tests"
+
+### Testing remove command
+
+$APP_BINARY rm -n cached
+
+if [ -n "$(ls -A "$GIT_CACHE_DIR" 2>/dev/null)" ]; then
+  echo -e "${FAILED}: Git Template remove command didn't remove the mirror"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: Git Template remove command removed the mirror"
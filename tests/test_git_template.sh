@@ -15,7 +15,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/mk-template" ]; then
+if [ ! -f "$TEMPLATES_DIR/mk-template/template.json" ]; then
   echo -e "${FAILED}: Git Template add command without name didn't create the template data"
   exit 1
 fi
@@ -31,7 +31,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/git_named" ]; then
+if [ ! -f "$TEMPLATES_DIR/git_named/template.json" ]; then
   echo -e "${FAILED}: Git Template add command with name didn't create the template data"
   exit 1
 fi
@@ -81,13 +81,13 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ -f "$TEMPLATES_DIR/test_dir" ]; then
+if [ -f "$TEMPLATES_DIR/test_dir/template.json" ]; then
   echo -e "${FAILED}: Git Template remove command didn't remove the template data"
   exit 1
 fi
 
-if [ -d "$TEMPLATES_DIR/test_dir.dir" ]; then
-  echo -e "${FAILED}: Git Template remove command didn't remove the .dir directory"
+if [ -d "$TEMPLATES_DIR/test_dir/payload" ]; then
+  echo -e "${FAILED}: Git Template remove command didn't remove the payload directory"
   exit 1
 fi
 
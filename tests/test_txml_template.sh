@@ -25,7 +25,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/$TXML_0_NAME" ]; then
+if [ ! -f "$TEMPLATES_DIR/$TXML_0_NAME/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command with out name didn't create the template data"
   exit 1
 fi
@@ -37,7 +37,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/$TXML_1_NAME" ]; then
+if [ ! -f "$TEMPLATES_DIR/$TXML_1_NAME/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command with out name didn't create the template data"
   exit 1
 fi
@@ -49,7 +49,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/$TXML_2_NAME" ]; then
+if [ ! -f "$TEMPLATES_DIR/$TXML_2_NAME/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command with out name didn't create the template data"
   exit 1
 fi
@@ -61,7 +61,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ -f "$TEMPLATES_DIR/$TXML_3_NAME" ]; then
+if [ -f "$TEMPLATES_DIR/$TXML_3_NAME/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command with out name created the template data with an unexpected name"
   exit 1
 fi
@@ -77,7 +77,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/1" ]; then
+if [ ! -f "$TEMPLATES_DIR/1/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command with name didn't create the template data"
   exit 1
 fi
@@ -137,6 +137,140 @@ fi
 
 echo -e "${SUCCESS}: TXML Template spawn command with custom name passed the tests"
 
+### Testing spawn command into an archive
+
+$APP_BINARY spawn -n "$TXML_1_NAME" --archive spawned.tar.gz
+
+if [ -e spawned.tar.gz ] && tar -tzf spawned.tar.gz | grep -q "crates/crate/sin_titulo.txt" && \
+   ! tar -tzf spawned.tar.gz | grep -q "crates/pepe"; then
+  echo -e "${SUCCESS}: TXML Template spawn command into an archive skipped the commands"
+else
+  echo -e "${FAILED}: TXML Template spawn command into an archive didn't pack the files without running the commands"
+  exit 1
+fi
+
+$APP_BINARY spawn -n "$TXML_1_NAME" --archive spawned.zip --allow-commands
+
+if ! unzip -l spawned.zip | grep -q "crates/pepe/"; then
+  echo -e "${FAILED}: TXML Template spawn command into an archive didn't run the allowed commands"
+  exit 1
+fi
+
+if ! $APP_BINARY spawn -n "$TXML_1_NAME" --archive - | tar -t | grep -q "Cargo.toml"; then
+  echo -e "${FAILED}: TXML Template spawn command didn't write the tar to the stdout"
+  exit 1
+fi
+
+# Messages never get mixed with the tar written to the stdout
+
+cat > noisy.xml <<'TXML'
+<Root><Directory name="noisy" color="red"><File name="inside" extension="txt"/></Directory></Root>
+TXML
+
+$APP_BINARY add -p noisy.xml 2> /dev/null
+
+if ! $APP_BINARY spawn -n noisy --archive - 2> noisy_stderr | tar -t > noisy_listing || \
+   ! grep -q "inside.txt" noisy_listing || ! grep -q "Unknown attribute" noisy_stderr; then
+  echo -e "${FAILED}: TXML Template spawn command mixed its messages with the tar in the stdout"
+  exit 1
+fi
+
+$APP_BINARY rm -n noisy
+
+echo -e "${SUCCESS}: TXML Template spawn command into an archive passed the tests"
+
+### Testing edit command
+
+TXML_0_PAYLOAD="$TEMPLATES_DIR/$TXML_0_NAME/payload/template.txml"
+
+printf '#!/bin/sh\necho "<Root></File>" > "$1"\n' > invalid_editor.sh
+printf '#!/bin/sh\nsed -i "s/name=\\"txml\\"/name=\\"edited\\"/" "$1"\n' > valid_editor.sh
+chmod +x invalid_editor.sh valid_editor.sh
+
+EDITOR=./invalid_editor.sh $APP_BINARY edit -n "$TXML_0_NAME" < /dev/null
+
+if ! cmp -s "$TXML_0" "$TXML_0_PAYLOAD"; then
+  echo -e "${FAILED}: TXML Template edit command kept an invalid TXML"
+  exit 1
+fi
+
+EDITOR=./valid_editor.sh $APP_BINARY edit -n "$TXML_0_NAME"
+
+if ! grep -q 'name="edited"' "$TXML_0_PAYLOAD"; then
+  echo -e "${FAILED}: TXML Template edit command didn't save the changes"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: TXML Template edit command passed the tests"
+
+### Testing tag, list and search commands
+
+cat > tagged.xml <<'TXML'
+<Root><Metadata author="Jane Doe" description="Tagged service" tags="Rust, cli"/><File name="main" extension="rs"/></Root>
+TXML
+
+$APP_BINARY add -p tagged.xml
+
+if ! grep -q '"rust"' "$TEMPLATES_DIR/tagged/template.json" || ! grep -q '"cli"' "$TEMPLATES_DIR/tagged/template.json"; then
+  echo -e "${FAILED}: TXML Template add command didn't save the tags of the Metadata"
+  exit 1
+fi
+
+$APP_BINARY tag -n tagged add web
+$APP_BINARY tag -n tagged rm cli
+
+if [ "$($APP_BINARY list --tag rust --tag web)" != "tagged [rust, web]" ] || [ -n "$($APP_BINARY list --tag cli)" ]; then
+  echo -e "${FAILED}: TXML Template list command didn't filter by the tags"
+  exit 1
+fi
+
+if ! $APP_BINARY search jane | grep -q "^tagged" || ! $APP_BINARY search SERVICE | grep -q "^tagged" || \
+   $APP_BINARY search nothing-like-this | grep -q "^tagged"; then
+  echo -e "${FAILED}: TXML Template search command didn't match the author and description"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: TXML Template tag, list and search commands passed the tests"
+
+### Testing --format output
+
+if ! $APP_BINARY list --format json | grep -q '"tags": \[' || \
+   ! $APP_BINARY info -n tagged --format json | grep -q '"description": "Tagged service"' || \
+   ! $APP_BINARY info -n tagged --format yaml | grep -q '^class: "txml"$' || \
+   ! $APP_BINARY list --format table | grep -q '^NAME  *CLASS  *SOURCE  *DESCRIPTION  *VERSION  *TAGS  *VARIABLES$'; then
+  echo -e "${FAILED}: TXML Template list and info commands didn't write the formatted output"
+  exit 1
+fi
+
+if ! $APP_BINARY validate -n tagged -n missing --format json | tr -d ' \n' | \
+   grep -q '"name":"tagged","valid":true,"problems":\[\]},{"name":"missing","valid":false'; then
+  echo -e "${FAILED}: TXML Template validate command didn't report the valid and missing templates"
+  exit 1
+fi
+
+# A wrong format is an error in the stderr, nothing is written to the stdout
+
+format_stdout=$($APP_BINARY list --format xml 2> format_stderr)
+
+if [ $? -eq 0 ] || [ -n "$format_stdout" ] || [ ! -s format_stderr ]; then
+  echo -e "${FAILED}: TXML Template list command didn't fail in the stderr with a wrong format"
+  exit 1
+fi
+
+# A reader that stops early doesn't make the commands panic
+
+$APP_BINARY info -n tagged 2> pipe_stderr | true
+$APP_BINARY list --format yaml 2>> pipe_stderr | head -0
+
+if grep -q "panicked" pipe_stderr; then
+  echo -e "${FAILED}: TXML Template info and list commands panicked on a closed pipe"
+  exit 1
+fi
+
+$APP_BINARY rm -n tagged
+
+echo -e "${SUCCESS}: TXML Template --format output passed the tests"
+
 ### Testing remove command
 
 $APP_BINARY rm -n 0
@@ -146,13 +280,13 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ -f "$TEMPLATES_DIR/0" ]; then
+if [ -f "$TEMPLATES_DIR/0/template.json" ]; then
   echo -e "${FAILED}: TXML Template remove command didn't remove the template data"
   exit 1
 fi
 
-if [ -d "$TEMPLATES_DIR/0.txml" ]; then
-  echo -e "${FAILED}: TXML Template remove command didn't remove the .txml file"
+if [ -d "$TEMPLATES_DIR/0" ]; then
+  echo -e "${FAILED}: TXML Template remove command didn't remove the template directory"
   exit 1
 fi
 
@@ -167,7 +301,7 @@ if [ $? -ne 0 ]; then
   exit 1
 fi
 
-if [ ! -f "$TEMPLATES_DIR/$TXML_4_NAME" ]; then
+if [ ! -f "$TEMPLATES_DIR/$TXML_4_NAME/template.json" ]; then
   echo -e "${FAILED}: TXML Template add command didn't create the template data whe using variable tags"
   exit 1
 fi
@@ -197,3 +331,49 @@ if [ ! "$EXPECTED_CONTENT" = "file1 content" ]; then
 fi
 
 echo -e "${SUCCESS}: TXML Template spawn command with variable tags passed the tests"
+
+### Testing spawn command with given variables and confirmation
+
+echo "n" | $APP_BINARY spawn -n "$TXML_4_NAME" --var VAR1=confirmed --var VAR2=given --confirm
+
+if [ -e "./confirmed" ]; then
+  echo -e "${FAILED}: TXML Template spawn command wrote the template without confirmation"
+  exit 1
+fi
+
+echo "y" | $APP_BINARY spawn -n "$TXML_4_NAME" --var VAR1=confirmed --var VAR2=given --confirm
+
+if [ "$(cat confirmed/given.txt 2>/dev/null)" != "given content" ]; then
+  echo -e "${FAILED}: TXML Template spawn command didn't use the given variables once confirmed"
+  exit 1
+fi
+
+# The commands of the template only run once the files are confirmed, where they were moved
+
+cat > commanded.xml <<TXML
+<Root><Directory name="commanded" in_command="touch $PWD/commanded_marker"><File name="inside" extension="txt" command="touch ran_here"/></Directory></Root>
+TXML
+
+$APP_BINARY add -p commanded.xml 2> /dev/null
+echo "n" | $APP_BINARY spawn -n commanded --confirm
+
+if [ -e "./commanded_marker" ] || [ -e "./commanded" ]; then
+  echo -e "${FAILED}: TXML Template spawn command ran the commands without confirmation"
+  exit 1
+fi
+
+echo "y" | $APP_BINARY spawn -n commanded -o commanded_confirmed --confirm
+
+if [ ! -e "./commanded_marker" ] || [ ! -e "./commanded_confirmed/ran_here" ]; then
+  echo -e "${FAILED}: TXML Template spawn command didn't run the commands once confirmed"
+  exit 1
+fi
+
+$APP_BINARY rm -n commanded
+
+if ! $APP_BINARY spawn < /dev/null | grep -q "USAGE"; then
+  echo -e "${FAILED}: TXML Template spawn command without a name didn't show the usage outside a terminal"
+  exit 1
+fi
+
+echo -e "${SUCCESS}: TXML Template spawn command with given variables and confirmation passed the tests"